@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::{mem::swap, sync::Arc};
 
 use crate::{
 	aabb::AABB,
@@ -6,8 +6,9 @@ use crate::{
 	hittable::{HitRecord, Hittable},
 	hittable_list::HittableList,
 	material::Material,
+	quad::Quad,
 	ray::Ray,
-	vec3::Point3,
+	vec3::{cross, dot, Point3, Vec3},
 };
 
 pub struct Box {
@@ -17,12 +18,12 @@ pub struct Box {
 }
 
 impl Box {
-	pub fn new(p0: &Point3, p1: &Point3, ptr: Rc<dyn Material,>,) -> Self {
+	pub fn new(p0: &Point3, p1: &Point3, ptr: Arc<dyn Material,>,) -> Self {
 		let box_min = *p0;
 		let box_max = *p1;
 
 		let mut sides = HittableList::default();
-		sides.add(Rc::new(XYRect::new(
+		sides.add(Arc::new(XYRect::new(
 			p0.x(),
 			p1.x(),
 			p0.y(),
@@ -30,7 +31,7 @@ impl Box {
 			p1.z(),
 			ptr.clone(),
 		),),);
-		sides.add(Rc::new(XYRect::new(
+		sides.add(Arc::new(XYRect::new(
 			p0.x(),
 			p1.x(),
 			p0.y(),
@@ -39,7 +40,7 @@ impl Box {
 			ptr.clone(),
 		),),);
 
-		sides.add(Rc::new(XZRect::new(
+		sides.add(Arc::new(XZRect::new(
 			p0.x(),
 			p1.x(),
 			p0.z(),
@@ -47,7 +48,7 @@ impl Box {
 			p1.y(),
 			ptr.clone(),
 		),),);
-		sides.add(Rc::new(XZRect::new(
+		sides.add(Arc::new(XZRect::new(
 			p0.x(),
 			p1.x(),
 			p0.z(),
@@ -56,7 +57,7 @@ impl Box {
 			ptr.clone(),
 		),),);
 
-		sides.add(Rc::new(YZRect::new(
+		sides.add(Arc::new(YZRect::new(
 			p0.y(),
 			p1.y(),
 			p0.z(),
@@ -64,7 +65,7 @@ impl Box {
 			p1.x(),
 			ptr.clone(),
 		),),);
-		sides.add(Rc::new(YZRect::new(
+		sides.add(Arc::new(YZRect::new(
 			p0.y(),
 			p1.y(),
 			p0.z(),
@@ -79,6 +80,50 @@ impl Box {
 			sides,
 		}
 	}
+
+	/// A parallelepiped with a corner at `corner` and the edges `a`, `b` and `c` leaving it. Its
+	/// sides are `Quad`s with outward facing normals, so it can be tilted in any direction.
+	pub fn oriented(
+		corner: Point3,
+		mut a: Vec3,
+		mut b: Vec3,
+		c: Vec3,
+		ptr: Arc<dyn Material,>,
+	) -> Self {
+		// Quad normals follow the edge order, which assumes a right-handed set of edges.
+		if dot(&cross(&a, &b,), &c,) < 0.0 {
+			swap(&mut a, &mut b,);
+		}
+
+		let mut sides = HittableList::default();
+		sides.add(Arc::new(Quad::new(corner, b, a, ptr.clone(),),),);
+		sides.add(Arc::new(Quad::new(corner + c, a, b, ptr.clone(),),),);
+		sides.add(Arc::new(Quad::new(corner, c, b, ptr.clone(),),),);
+		sides.add(Arc::new(Quad::new(corner + a, b, c, ptr.clone(),),),);
+		sides.add(Arc::new(Quad::new(corner, a, c, ptr.clone(),),),);
+		sides.add(Arc::new(Quad::new(corner + b, c, a, ptr,),),);
+
+		let mut box_min = corner;
+		let mut box_max = corner;
+		for i in 1 .. 8 {
+			let mut p = corner;
+			for (bit, edge,) in [a, b, c,].iter().enumerate() {
+				if i & (1 << bit) != 0 {
+					p += *edge;
+				}
+			}
+			for axis in 0 .. 3 {
+				box_min[axis] = box_min[axis].min(p[axis],);
+				box_max[axis] = box_max[axis].max(p[axis],);
+			}
+		}
+
+		Box {
+			box_min,
+			box_max,
+			sides,
+		}
+	}
 }
 
 impl Hittable for Box {
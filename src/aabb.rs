@@ -1,7 +1,7 @@
 use std::mem::swap;
 
 use crate::ray::Ray;
-use crate::vec3::Point3;
+use crate::vec3::{Point3, Vec3};
 
 #[derive(Default, Clone)]
 pub struct AABB {
@@ -22,12 +22,34 @@ impl AABB {
 		self.maximum
 	}
 
-	pub fn hit(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
+	pub fn centroid(&self) -> Point3 {
+		0.5 * (self.minimum + self.maximum)
+	}
+
+	pub fn surface_area(&self) -> f64 {
+		let d = self.maximum - self.minimum;
+		2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+	}
+
+	pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+		let d = r.direction();
+		let inv_dir = Vec3::new(1.0 / d.x(), 1.0 / d.y(), 1.0 / d.z());
+		self.hit_with_inverse(&r.origin(), &inv_dir, t_min, t_max)
+	}
+
+	/// Like `hit`, but with the reciprocal of the ray direction computed up front, so that a
+	/// traversal testing many boxes against the same ray only divides once.
+	pub fn hit_with_inverse(
+		&self,
+		origin: &Point3,
+		inv_dir: &Vec3,
+		mut t_min: f64,
+		mut t_max: f64,
+	) -> bool {
 		for a in 0..3 {
-			let inv_d = 1.0 / r.direction()[a];
-			let mut t0 = (self.min()[a] - r.origin()[a]) * inv_d;
-			let mut t1 = (self.max()[a] - r.origin()[a]) * inv_d;
-			if inv_d < 0.0 {
+			let mut t0 = (self.min()[a] - origin[a]) * inv_dir[a];
+			let mut t1 = (self.max()[a] - origin[a]) * inv_dir[a];
+			if inv_dir[a] < 0.0 {
 				swap(&mut t0, &mut t1);
 			}
 			t_min = if t0 > t_min { t0 } else { t_min };
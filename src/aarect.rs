@@ -1,15 +1,16 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::{
 	aabb::AABB,
-	hittable::{HitRecord, Hittable},
+	hittable::{area_pdf_value, HitRecord, Hittable},
 	material::Material,
 	ray::Ray,
+	sampler::Sampler,
 	vec3::{Point3, Vec3},
 };
 
 pub struct XYRect {
-	mp: Rc<dyn Material,>,
+	mp: Arc<dyn Material,>,
 	x0: f64,
 	x1: f64,
 	y0: f64,
@@ -18,7 +19,7 @@ pub struct XYRect {
 }
 
 impl XYRect {
-	pub fn new(x0: f64, x1: f64, y0: f64, y1: f64, k: f64, mp: Rc<dyn Material,>,) -> Self {
+	pub fn new(x0: f64, x1: f64, y0: f64, y1: f64, k: f64, mp: Arc<dyn Material,>,) -> Self {
 		XYRect {
 			mp,
 			x0,
@@ -63,10 +64,22 @@ impl Hittable for XYRect {
 			Point3::new(self.x1, self.y1, self.k + 0.0001,),
 		),)
 	}
+
+	fn pdf_value(&self, origin: &Point3, direction: &Vec3,) -> f64 {
+		let area = (self.x1 - self.x0) * (self.y1 - self.y0);
+		area_pdf_value(self, area, origin, direction,)
+	}
+
+	fn random(&self, origin: &Point3, sampler: &mut dyn Sampler,) -> Vec3 {
+		let (u0, u1,) = sampler.get_2d();
+		let x = self.x0 + u0 * (self.x1 - self.x0);
+		let y = self.y0 + u1 * (self.y1 - self.y0);
+		Point3::new(x, y, self.k,) - *origin
+	}
 }
 
 pub struct XZRect {
-	mp: Rc<dyn Material,>,
+	mp: Arc<dyn Material,>,
 	x0: f64,
 	x1: f64,
 	z0: f64,
@@ -75,7 +88,7 @@ pub struct XZRect {
 }
 
 impl XZRect {
-	pub fn new(x0: f64, x1: f64, z0: f64, z1: f64, k: f64, mp: Rc<dyn Material,>,) -> Self {
+	pub fn new(x0: f64, x1: f64, z0: f64, z1: f64, k: f64, mp: Arc<dyn Material,>,) -> Self {
 		XZRect {
 			mp,
 			x0,
@@ -120,10 +133,22 @@ impl Hittable for XZRect {
 			Point3::new(self.x1, self.k + 0.0001, self.z1,),
 		),)
 	}
+
+	fn pdf_value(&self, origin: &Point3, direction: &Vec3,) -> f64 {
+		let area = (self.x1 - self.x0) * (self.z1 - self.z0);
+		area_pdf_value(self, area, origin, direction,)
+	}
+
+	fn random(&self, origin: &Point3, sampler: &mut dyn Sampler,) -> Vec3 {
+		let (u0, u1,) = sampler.get_2d();
+		let x = self.x0 + u0 * (self.x1 - self.x0);
+		let z = self.z0 + u1 * (self.z1 - self.z0);
+		Point3::new(x, self.k, z,) - *origin
+	}
 }
 
 pub struct YZRect {
-	mp: Rc<dyn Material,>,
+	mp: Arc<dyn Material,>,
 	y0: f64,
 	y1: f64,
 	z0: f64,
@@ -132,7 +157,7 @@ pub struct YZRect {
 }
 
 impl YZRect {
-	pub fn new(y0: f64, y1: f64, z0: f64, z1: f64, k: f64, mp: Rc<dyn Material,>,) -> Self {
+	pub fn new(y0: f64, y1: f64, z0: f64, z1: f64, k: f64, mp: Arc<dyn Material,>,) -> Self {
 		YZRect {
 			mp,
 			y0,
@@ -177,4 +202,16 @@ impl Hittable for YZRect {
 			Point3::new(self.k + 0.0001, self.y1, self.z1,),
 		),)
 	}
+
+	fn pdf_value(&self, origin: &Point3, direction: &Vec3,) -> f64 {
+		let area = (self.y1 - self.y0) * (self.z1 - self.z0);
+		area_pdf_value(self, area, origin, direction,)
+	}
+
+	fn random(&self, origin: &Point3, sampler: &mut dyn Sampler,) -> Vec3 {
+		let (u0, u1,) = sampler.get_2d();
+		let y = self.y0 + u0 * (self.y1 - self.y0);
+		let z = self.z0 + u1 * (self.z1 - self.z0);
+		Point3::new(self.k, y, z,) - *origin
+	}
 }
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::{surrounding_box, AABB},
+	hittable::{HitRecord, Hittable},
+	quat::Quat,
+	ray::Ray,
+	vec3::{Point3, Vec3},
+};
+
+/// Steps per keyframe interval at which the swept bounding box is sampled.
+const BOUNDING_BOX_STEPS: usize = 8;
+
+/// The pose of an object at `time`: rotated about its origin, then translated.
+#[derive(Clone, Copy,)]
+pub struct Keyframe {
+	pub time:        f64,
+	pub translation: Vec3,
+	pub rotation:    Quat,
+}
+
+impl Keyframe {
+	pub fn new(time: f64, translation: Vec3, rotation: Quat,) -> Self {
+		Keyframe {
+			time,
+			translation,
+			rotation: rotation.normalize(),
+		}
+	}
+}
+
+/// Moves an object through a list of keyframes, so that it blurs over the shutter interval.
+/// Translations are interpolated linearly and rotations with slerp, before the first and after
+/// the last keyframe the object holds still.
+pub struct AnimatedTransform {
+	ptr:       Arc<dyn Hittable,>,
+	keyframes: Vec<Keyframe,>,
+}
+
+impl AnimatedTransform {
+	/// Panics without keyframes.
+	pub fn new(ptr: Arc<dyn Hittable,>, mut keyframes: Vec<Keyframe,>,) -> Self {
+		assert!(!keyframes.is_empty(), "AnimatedTransform needs at least one keyframe");
+		keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time,).unwrap(),);
+
+		AnimatedTransform { ptr, keyframes, }
+	}
+
+	/// The interpolated translation and rotation at `time`.
+	pub fn pose(&self, time: f64,) -> (Vec3, Quat,) {
+		let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time,);
+		if next == 0 {
+			let first = &self.keyframes[0];
+			return (first.translation, first.rotation,);
+		}
+		if next == self.keyframes.len() {
+			let last = &self.keyframes[next - 1];
+			return (last.translation, last.rotation,);
+		}
+
+		let (a, b,) = (&self.keyframes[next - 1], &self.keyframes[next],);
+		let t = (time - a.time) / (b.time - a.time);
+		(
+			a.translation + t * (b.translation - a.translation),
+			Quat::slerp(&a.rotation, &b.rotation, t,),
+		)
+	}
+}
+
+impl Hittable for AnimatedTransform {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let (translation, rotation,) = self.pose(r.time(),);
+		let inverse = rotation.conjugate();
+
+		let object_r = Ray::new(
+			inverse.rotate(&(r.origin() - translation),),
+			inverse.rotate(&r.direction(),),
+			r.time(),
+		);
+
+		self.ptr.hit(&object_r, t_min, t_max,).map(|mut rec| {
+			rec.p = rotation.rotate(&rec.p,) + translation;
+			rec.normal = rotation.rotate(&rec.normal,);
+			rec
+		},)
+	}
+
+	/// The object stays inside the bounding sphere of its own box, so the swept box is found by
+	/// following the center of that sphere. Between two samples the center travels at most the
+	/// translation plus the arc its distance from the origin sweeps, so padding every sample by
+	/// half of that keeps the box conservative.
+	fn bounding_box(&self, time0: f64, time1: f64,) -> Option<AABB,> {
+		let bbox = self.ptr.bounding_box(time0, time1,)?;
+		let center = 0.5 * (bbox.min() + bbox.max());
+		let radius = 0.5 * (bbox.max() - bbox.min()).length();
+
+		let mut times = vec![time0];
+		times.extend(
+			self.keyframes
+				.iter()
+				.map(|keyframe| keyframe.time,)
+				.filter(|&time| time0 < time && time < time1,),
+		);
+		times.push(time1,);
+
+		let sample = |time: f64| {
+			let (translation, rotation,) = self.pose(time,);
+			(rotation.rotate(&center,) + translation, translation, rotation,)
+		};
+		let sphere_box = |p: Point3, radius: f64| {
+			let extent = Vec3::new(radius, radius, radius,);
+			AABB::new(p - extent, p + extent,)
+		};
+
+		let mut previous = sample(time0,);
+		let mut result = sphere_box(previous.0, radius,);
+
+		for interval in times.windows(2,) {
+			for step in 1 ..= BOUNDING_BOX_STEPS {
+				let time = interval[0] + (interval[1] - interval[0]) * step as f64 / BOUNDING_BOX_STEPS as f64;
+				let current = sample(time,);
+
+				// The rotation between the samples, the short way around.
+				let (_, degrees,) = (current.2 * previous.2.conjugate()).to_axis_angle();
+				let angle = degrees.min(360.0 - degrees,).to_radians();
+				let travel = (current.1 - previous.1).length() + center.length() * angle;
+				result = surrounding_box(result, sphere_box(previous.0, radius + 0.5 * travel,),);
+				result = surrounding_box(result, sphere_box(current.0, radius + 0.5 * travel,),);
+
+				previous = current;
+			}
+		}
+
+		Some(result,)
+	}
+}
@@ -1,81 +1,79 @@
-use std::{
-	cmp::{Ordering, Ordering::Less},
-	rc::Rc,
-};
+use std::{ops::Range, sync::Arc};
 
 use crate::{
 	aabb::{AABB, surrounding_box},
 	hittable::{HitRecord, Hittable},
 	hittable_list::HittableList,
 	ray::Ray,
-	util::random_i32_in_range,
+	vec3::{Point3, Vec3},
 };
 
+/// Most objects the world BVH puts in a single leaf.
+pub const MAX_OBJECTS_IN_LEAF: usize = 2;
+/// Buckets along an axis in which centroids are binned to evaluate splits.
+const BINS: usize = 12;
+/// Cost of visiting a node relative to intersecting a primitive.
+const TRAVERSAL_COST: f64 = 0.125;
+/// Depth after which ranges are split at the median instead of by the heuristic, which keeps
+/// trees over up to 2^32 primitives within `STACK_SIZE` levels.
+const MAX_SAH_DEPTH: usize = 32;
+/// Nodes the traversal of a flattened BVH can put aside.
+const STACK_SIZE: usize = 64;
+
 pub struct BVHNode {
-	pub left:  Rc<dyn Hittable,>,
-	pub right: Rc<dyn Hittable,>,
+	pub left:  Arc<dyn Hittable,>,
+	pub right: Arc<dyn Hittable,>,
 	pub aabb:  AABB,
 }
 
 impl BVHNode {
-	pub fn from_list(list: &HittableList, time0: f64, time1: f64,) -> Self {
-		BVHNode::new(&list.objects, 0, list.objects.len(), time0, time1,)
-	}
-
-	pub fn new(
-		src_objects: &Vec<Rc<dyn Hittable,>,>,
-		start: usize,
-		end: usize,
+	/// Builds a BVH over `objects` with the surface area heuristic and returns its root. A leaf
+	/// holding a single object is that object itself, larger leaves are `HittableList`s.
+	///
+	/// Panics when an object has no bounding box.
+	pub fn build(
+		objects: &[Arc<dyn Hittable,>],
 		time0: f64,
 		time1: f64,
-	) -> Self {
-		let mut objects = src_objects.clone();
+		max_leaf_size: usize,
+	) -> Arc<dyn Hittable,> {
+		let boxes: Vec<_,> = objects
+			.iter()
+			.map(|object| {
+				object
+					.bounding_box(time0, time1,)
+					.expect("No bounding box in BVHNode::build()",)
+			},)
+			.collect();
 
-		let axis = random_i32_in_range(0, 2,);
-		let comparator = if axis == 0 {
-			box_x_compare
-		} else if axis == 1 {
-			box_y_compare
-		} else {
-			box_z_compare
-		};
-
-		let left;
-		let right;
-
-		let object_span = end - start;
-
-		if object_span == 1 {
-			left = objects[start].clone();
-			right = left.clone();
-		} else if object_span == 2 {
-			match comparator(&objects[start], &objects[start + 1],) {
-				Ordering::Less => {
-					left = objects[start].clone();
-					right = objects[start + 1].clone();
-				},
-				_ => {
-					left = objects[start + 1].clone();
-					right = objects[start].clone();
-				},
-			}
-		} else {
-			objects.sort_by(comparator,);
+		let (root, order,) = build_sah(&boxes, max_leaf_size,);
+		BVHNode::from_build_node(root, objects, &order,)
+	}
 
-			let mid = start + object_span / 2;
-			left = Rc::new(BVHNode::new(&objects, start, mid, time0, time1,),);
-			right = Rc::new(BVHNode::new(&objects, mid, end, time0, time1,),);
+	fn from_build_node(
+		node: BuildNode,
+		objects: &[Arc<dyn Hittable,>],
+		order: &[usize],
+	) -> Arc<dyn Hittable,> {
+		match node {
+			BuildNode::Leaf {
+				start, count: 1, ..
+			} => objects[order[start]].clone(),
+			BuildNode::Leaf { start, count, .. } => Arc::new(HittableList {
+				objects: order[start .. start + count]
+					.iter()
+					.map(|&i| objects[i].clone(),)
+					.collect(),
+			},),
+			BuildNode::Interior { aabb, children, .. } => {
+				let [left, right,] = *children;
+				Arc::new(BVHNode {
+					left: BVHNode::from_build_node(left, objects, order,),
+					right: BVHNode::from_build_node(right, objects, order,),
+					aabb,
+				},)
+			},
 		}
-
-		let aabb = surrounding_box(
-			left.bounding_box(time0, time1,)
-				.expect("No bounding box in BVHNode::new()",),
-			right
-				.bounding_box(time0, time1,)
-				.expect("No bounding box in BVHNode::new()",),
-		);
-
-		BVHNode { left, right, aabb, }
 	}
 }
 
@@ -92,31 +90,557 @@ impl Hittable for BVHNode {
 		}
 	}
 
-	fn bounding_box(&self, time0: f64, time1: f64,) -> Option<AABB,> { Some(self.aabb.clone(),) }
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> { Some(self.aabb.clone(),) }
 }
 
-#[inline]
-fn box_compare(a: &Rc<dyn Hittable,>, b: &Rc<dyn Hittable,>, axis: usize,) -> Ordering {
-	let box_a = a
-		.bounding_box(0.0, 0.0,)
-		.expect("No bounding box in BVHNode::new()",);
-	let box_b = b
-		.bounding_box(0.0, 0.0,)
-		.expect("No bounding box in BVHNode::new()",);
+/// A BVH over scene objects, stored as a flat array of nodes in depth-first order and traversed
+/// without recursion.
+pub struct LinearBVH {
+	objects: Vec<Arc<dyn Hittable,>,>,
+	nodes:   Vec<LinearNode,>,
+}
+
+impl LinearBVH {
+	/// Builds the BVH with the surface area heuristic, panics when an object has no bounding box.
+	pub fn build(
+		objects: &[Arc<dyn Hittable,>],
+		time0: f64,
+		time1: f64,
+		max_leaf_size: usize,
+	) -> Self {
+		let boxes: Vec<_,> = objects
+			.iter()
+			.map(|object| {
+				object
+					.bounding_box(time0, time1,)
+					.expect("No bounding box in LinearBVH::build()",)
+			},)
+			.collect();
 
-	box_a.min().e[axis]
-		.partial_cmp(&box_b.min().e[axis],)
-		.unwrap_or(Less,)
+		let (root, order,) = build_sah(&boxes, max_leaf_size,);
+		let mut nodes = Vec::new();
+		flatten(&root, &mut nodes,);
+
+		LinearBVH {
+			objects: order.iter().map(|&i| objects[i].clone(),).collect(),
+			nodes,
+		}
+	}
+
+	pub fn node_count(&self,) -> usize { self.nodes.len() }
 }
 
-fn box_x_compare(a: &Rc<dyn Hittable,>, b: &Rc<dyn Hittable,>,) -> Ordering {
-	box_compare(a, b, 0,)
+impl Hittable for LinearBVH {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let mut closest = None;
+
+		traverse(&self.nodes, r, t_min, t_max, |range, mut closest_so_far| {
+			for object in &self.objects[range] {
+				if let Some(rec,) = object.hit(r, t_min, closest_so_far,) {
+					closest_so_far = rec.t;
+					closest = Some(rec,);
+				}
+			}
+			closest_so_far
+		},);
+
+		closest
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> {
+		self.nodes.first().map(|root| root.aabb.clone(),)
+	}
 }
 
-fn box_y_compare(a: &Rc<dyn Hittable,>, b: &Rc<dyn Hittable,>,) -> Ordering {
-	box_compare(a, b, 1,)
+/// Puts every bounded object of `list` in a `LinearBVH`. Objects without a bounding box, like
+/// `Plane` or a list holding one, cannot be sorted into a tree and are kept beside it.
+pub fn accelerate(list: &HittableList, time0: f64, time1: f64,) -> HittableList {
+	let (bounded, unbounded,): (Vec<_,>, Vec<_,>,) = list
+		.objects
+		.iter()
+		.cloned()
+		.partition(|object| object.bounding_box(time0, time1,).is_some(),);
+
+	let mut result = HittableList {
+		objects: unbounded,
+	};
+	if !bounded.is_empty() {
+		result.add(Arc::new(LinearBVH::build(
+			&bounded,
+			time0,
+			time1,
+			MAX_OBJECTS_IN_LEAF,
+		),),);
+	}
+	result
 }
 
-fn box_z_compare(a: &Rc<dyn Hittable,>, b: &Rc<dyn Hittable,>,) -> Ordering {
-	box_compare(a, b, 2,)
+/// A node of a BVH under construction, independent of what its primitives are. Leaves cover
+/// `order[start .. start + count]` of the primitive order the builder returns.
+pub enum BuildNode {
+	Leaf {
+		aabb:  AABB,
+		start: usize,
+		count: usize,
+	},
+	Interior {
+		aabb:     AABB,
+		/// The axis the children were split along.
+		axis:     usize,
+		children: Box<[BuildNode; 2],>,
+	},
+}
+
+impl BuildNode {
+	pub fn aabb(&self,) -> &AABB {
+		match self {
+			BuildNode::Leaf { aabb, .. } | BuildNode::Interior { aabb, .. } => aabb,
+		}
+	}
+}
+
+/// Builds a BVH over primitives with the given bounding boxes. Splits are chosen by the surface
+/// area heuristic over binned centroids, and a range becomes a leaf once it holds at most
+/// `max_leaf_size` primitives and splitting it does not pay off. Returns the root and the order
+/// of the primitives the leaves index into.
+///
+/// The build only permutes a single index array and is deterministic, so the same boxes always
+/// give the same tree.
+pub fn build_sah(boxes: &[AABB], max_leaf_size: usize,) -> (BuildNode, Vec<usize,>,) {
+	assert!(!boxes.is_empty(), "Cannot build a BVH without primitives");
+
+	let builder = SahBuilder {
+		boxes,
+		centroids: boxes.iter().map(AABB::centroid,).collect(),
+		max_leaf_size: max_leaf_size.max(1,),
+	};
+	let mut order: Vec<_,> = (0 .. boxes.len()).collect();
+	let root = builder.build(&mut order, 0, 0,);
+
+	(root, order,)
+}
+
+/// A node of a flattened BVH. Interior nodes have `count == 0`, their first child directly
+/// follows them in the node array and `offset` is the index of the second. Leaves cover the
+/// primitives `offset .. offset + count`.
+pub struct LinearNode {
+	pub aabb:   AABB,
+	pub offset: usize,
+	pub count:  usize,
+	/// The axis the children of an interior node were split along.
+	pub axis:   usize,
+}
+
+/// Appends `node` and its subtree to `nodes` in depth-first order. Returns the index of `node`.
+pub fn flatten(node: &BuildNode, nodes: &mut Vec<LinearNode,>,) -> usize {
+	let index = nodes.len();
+	nodes.push(LinearNode {
+		aabb:   node.aabb().clone(),
+		offset: 0,
+		count:  0,
+		axis:   0,
+	},);
+
+	match node {
+		BuildNode::Leaf { start, count, .. } => {
+			nodes[index].offset = *start;
+			nodes[index].count = *count;
+		},
+		BuildNode::Interior { axis, children, .. } => {
+			nodes[index].axis = *axis;
+			flatten(&children[0], nodes,);
+			nodes[index].offset = flatten(&children[1], nodes,);
+		},
+	}
+
+	index
+}
+
+/// Visits the leaves of a flattened BVH whose boxes `r` passes through, the nearer child of every
+/// node first. `leaf` gets the primitives of a leaf and the distance to the closest hit so far,
+/// and returns the updated distance, so that farther nodes can be skipped.
+pub fn traverse(
+	nodes: &[LinearNode],
+	r: &Ray,
+	t_min: f64,
+	mut t_max: f64,
+	mut leaf: impl FnMut(Range<usize,>, f64,) -> f64,
+) {
+	if nodes.is_empty() {
+		return;
+	}
+
+	let origin = r.origin();
+	let d = r.direction();
+	let inv_dir = Vec3::new(1.0 / d.x(), 1.0 / d.y(), 1.0 / d.z(),);
+
+	let mut stack = [0; STACK_SIZE];
+	let mut stack_len = 0;
+	let mut index = 0;
+
+	loop {
+		let node = &nodes[index];
+		if node.aabb.hit_with_inverse(&origin, &inv_dir, t_min, t_max,) {
+			if node.count > 0 {
+				t_max = leaf(node.offset .. node.offset + node.count, t_max,);
+			} else {
+				// Rays going down the split axis meet the second child first.
+				let (near, far,) = if inv_dir[node.axis] < 0.0 {
+					(node.offset, index + 1,)
+				} else {
+					(index + 1, node.offset,)
+				};
+				stack[stack_len] = far;
+				stack_len += 1;
+				index = near;
+				continue;
+			}
+		}
+
+		if stack_len == 0 {
+			break;
+		}
+		stack_len -= 1;
+		index = stack[stack_len];
+	}
+}
+
+struct SahBuilder<'a,> {
+	boxes:         &'a [AABB],
+	centroids:     Vec<Point3,>,
+	max_leaf_size: usize,
+}
+
+#[derive(Clone, Default,)]
+struct Bin {
+	count: usize,
+	aabb:  Option<AABB,>,
+}
+
+impl SahBuilder<'_,> {
+	/// Builds the subtree over `indices`, which starts at `offset` in the full order and lies
+	/// `depth` levels below the root.
+	fn build(&self, indices: &mut [usize], offset: usize, depth: usize,) -> BuildNode {
+		let aabb = self.bounds(indices.iter().map(|&i| self.boxes[i].clone(),),);
+		let count = indices.len();
+		let leaf = || BuildNode::Leaf {
+			aabb: aabb.clone(),
+			start: offset,
+			count,
+		};
+
+		if count == 1 {
+			return leaf();
+		}
+
+		let centroid_bounds = self.bounds(
+			indices
+				.iter()
+				.map(|&i| AABB::new(self.centroids[i], self.centroids[i],),),
+		);
+		let node_area = aabb.surface_area();
+
+		// Deep down the ranges are halved, which bounds the depth for the traversal stack.
+		let split = if depth < MAX_SAH_DEPTH {
+			self.best_split(indices, &centroid_bounds, node_area,)
+		} else {
+			None
+		};
+
+		let leaf_cost = count as f64 * node_area;
+		let (axis, mid,) = match split {
+			Some((cost, ..,),) if count <= self.max_leaf_size && leaf_cost <= cost =>
+				return leaf(),
+			Some((_, axis, bin,),) => {
+				let mid = partition(indices, |i| self.bin(i, axis, &centroid_bounds,) < bin,);
+				(axis, mid,)
+			},
+			None if count <= self.max_leaf_size => return leaf(),
+			None => self.median_split(indices, &centroid_bounds,),
+		};
+
+		let (left, right,) = indices.split_at_mut(mid,);
+		BuildNode::Interior {
+			aabb,
+			axis,
+			children: Box::new([
+				self.build(left, offset, depth + 1,),
+				self.build(right, offset + mid, depth + 1,),
+			],),
+		}
+	}
+
+	/// Splits `indices` in two halves at the median centroid along the longest axis.
+	fn median_split(&self, indices: &mut [usize], centroid_bounds: &AABB,) -> (usize, usize,) {
+		let extent = centroid_bounds.max() - centroid_bounds.min();
+		let axis = if extent.x() > extent.y() && extent.x() > extent.z() {
+			0
+		} else if extent.y() > extent.z() {
+			1
+		} else {
+			2
+		};
+
+		let mid = indices.len() / 2;
+		indices.select_nth_unstable_by(mid, |&a, &b| {
+			self.centroids[a][axis]
+				.partial_cmp(&self.centroids[b][axis],)
+				.unwrap()
+		},);
+		(axis, mid,)
+	}
+
+	/// The cheapest split as its cost, axis and the first bin on the right side. Costs are left
+	/// unnormalized by the area of the node, which is `node_area`.
+	fn best_split(
+		&self,
+		indices: &[usize],
+		centroid_bounds: &AABB,
+		node_area: f64,
+	) -> Option<(f64, usize, usize,),> {
+		let mut best: Option<(f64, usize, usize,),> = None;
+
+		for axis in 0 .. 3 {
+			if centroid_bounds.max()[axis] <= centroid_bounds.min()[axis] {
+				continue;
+			}
+
+			let mut bins = vec![Bin::default(); BINS];
+			for &i in indices {
+				let bin = &mut bins[self.bin(i, axis, centroid_bounds,)];
+				bin.count += 1;
+				bin.aabb = Some(merge(bin.aabb.take(), &self.boxes[i],),);
+			}
+
+			// Sweep from the left for the cost of everything left of each split, then from the
+			// right to complete it.
+			let mut left = [(0, 0.0,); BINS];
+			let mut count = 0;
+			let mut aabb = None;
+			for split in 1 .. BINS {
+				count += bins[split - 1].count;
+				aabb = merge_bin(aabb, &bins[split - 1],);
+				left[split] = (count, area(&aabb,),);
+			}
+
+			let mut count = 0;
+			let mut aabb = None;
+			for split in (1 .. BINS).rev() {
+				count += bins[split].count;
+				aabb = merge_bin(aabb, &bins[split],);
+
+				let (left_count, left_area,) = left[split];
+				if count == 0 || left_count == 0 {
+					continue;
+				}
+
+				let cost = TRAVERSAL_COST * node_area
+					+ left_count as f64 * left_area
+					+ count as f64 * area(&aabb,);
+				if best.is_none_or(|(best_cost, ..,)| cost < best_cost,) {
+					best = Some((cost, axis, split,),);
+				}
+			}
+		}
+
+		best
+	}
+
+	fn bin(&self, i: usize, axis: usize, centroid_bounds: &AABB,) -> usize {
+		let min = centroid_bounds.min()[axis];
+		let extent = centroid_bounds.max()[axis] - min;
+		((BINS as f64 * (self.centroids[i][axis] - min) / extent) as usize).min(BINS - 1,)
+	}
+
+	fn bounds(&self, boxes: impl Iterator<Item = AABB,>,) -> AABB {
+		boxes
+			.reduce(surrounding_box,)
+			.expect("BVH nodes are never empty",)
+	}
+}
+
+fn merge(aabb: Option<AABB,>, other: &AABB,) -> AABB {
+	match aabb {
+		Some(aabb,) => surrounding_box(aabb, other.clone(),),
+		None => other.clone(),
+	}
+}
+
+fn merge_bin(aabb: Option<AABB,>, bin: &Bin,) -> Option<AABB,> {
+	match &bin.aabb {
+		Some(bin_box,) => Some(merge(aabb, bin_box,),),
+		None => aabb,
+	}
+}
+
+fn area(aabb: &Option<AABB,>,) -> f64 { aabb.as_ref().map_or(0.0, AABB::surface_area,) }
+
+/// Moves the indices matching `pred` to the front and returns how many there are.
+fn partition(indices: &mut [usize], pred: impl Fn(usize,) -> bool,) -> usize {
+	let mut mid = 0;
+	for j in 0 .. indices.len() {
+		if pred(indices[j],) {
+			indices.swap(mid, j,);
+			mid += 1;
+		}
+	}
+	mid
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{material::Lambertian, plane::Plane, sphere::Sphere, vec3::Color};
+
+	/// A small xorshift generator, so that every run tests the same scenes.
+	struct Rng(u64,);
+
+	impl Rng {
+		fn next(&mut self,) -> f64 {
+			self.0 ^= self.0 << 13;
+			self.0 ^= self.0 >> 7;
+			self.0 ^= self.0 << 17;
+			(self.0 >> 11) as f64 / (1u64 << 53) as f64
+		}
+
+		fn point(&mut self, scale: f64,) -> Point3 {
+			scale * Point3::new(self.next() - 0.5, self.next() - 0.5, self.next() - 0.5,)
+		}
+	}
+
+	fn random_boxes(count: usize, rng: &mut Rng,) -> Vec<AABB,> {
+		(0 .. count)
+			.map(|_| {
+				let min = rng.point(100.0,);
+				AABB::new(min, min + rng.point(4.0,) + Vec3::new(2.0, 2.0, 2.0,),)
+			},)
+			.collect()
+	}
+
+	fn random_spheres(count: usize, rng: &mut Rng,) -> Vec<Arc<dyn Hittable,>,> {
+		let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5,),),);
+		(0 .. count)
+			.map(|_| {
+				let center = rng.point(40.0,);
+				Arc::new(Sphere::new(center, 0.2 + 2.0 * rng.next(), material.clone(),),)
+					as Arc<dyn Hittable,>
+			},)
+			.collect()
+	}
+
+	fn flattened(boxes: &[AABB], max_leaf_size: usize,) -> (Vec<LinearNode,>, Vec<usize,>,) {
+		let (root, order,) = build_sah(boxes, max_leaf_size,);
+		let mut nodes = Vec::new();
+		flatten(&root, &mut nodes,);
+		(nodes, order,)
+	}
+
+	fn contains(outer: &AABB, inner: &AABB,) -> bool {
+		(0 .. 3).all(|c| outer.min()[c] <= inner.min()[c] && inner.max()[c] <= outer.max()[c],)
+	}
+
+	#[test]
+	fn repeated_builds_give_identical_trees() {
+		let boxes = random_boxes(1000, &mut Rng(0x9e37_79b9_7f4a_7c15,),);
+		let (nodes, order,) = flattened(&boxes, 4,);
+		let (again, again_order,) = flattened(&boxes.clone(), 4,);
+
+		assert_eq!(order, again_order);
+		assert_eq!(nodes.len(), again.len());
+		for (a, b,) in nodes.iter().zip(&again,) {
+			assert_eq!((a.offset, a.count, a.axis,), (b.offset, b.count, b.axis,));
+			assert_eq!(a.aabb.min().e, b.aabb.min().e);
+			assert_eq!(a.aabb.max().e, b.aabb.max().e);
+		}
+	}
+
+	#[test]
+	fn leaves_cover_every_primitive_once() {
+		let boxes = random_boxes(777, &mut Rng(12345,),);
+		let (nodes, order,) = flattened(&boxes, 4,);
+
+		let mut sorted = order.clone();
+		sorted.sort_unstable();
+		assert_eq!(sorted, (0 .. boxes.len()).collect::<Vec<_,>>());
+
+		let mut covered = vec![0; boxes.len()];
+		for (index, node,) in nodes.iter().enumerate() {
+			if node.count == 0 {
+				assert!(contains(&node.aabb, &nodes[index + 1].aabb,));
+				assert!(contains(&node.aabb, &nodes[node.offset].aabb,));
+				continue;
+			}
+			assert!(node.count <= 4);
+			for &primitive in &order[node.offset .. node.offset + node.count] {
+				assert!(contains(&node.aabb, &boxes[primitive],));
+				covered[primitive] += 1;
+			}
+		}
+		assert!(covered.iter().all(|&count| count == 1));
+	}
+
+	#[test]
+	fn identical_boxes_still_build() {
+		let boxes = vec![AABB::new(Point3::default(), Point3::new(1.0, 1.0, 1.0,),); 100];
+		let (nodes, order,) = flattened(&boxes, 2,);
+		assert_eq!(order.len(), 100);
+		assert_eq!(nodes.iter().map(|node| node.count,).sum::<usize>(), 100);
+	}
+
+	#[test]
+	fn both_bvhs_find_the_same_hits() {
+		let mut rng = Rng(0xdead_beef,);
+		let objects = random_spheres(300, &mut rng,);
+		let tree = BVHNode::build(&objects, 0.0, 1.0, MAX_OBJECTS_IN_LEAF,);
+		let linear = LinearBVH::build(&objects, 0.0, 1.0, MAX_OBJECTS_IN_LEAF,);
+		let list = HittableList {
+			objects: objects.clone(),
+		};
+
+		let mut hits = 0;
+		for _ in 0 .. 2000 {
+			let ray = Ray::new(rng.point(120.0,), rng.point(1.0,), 0.0,);
+			let expected = list.hit(&ray, 0.001, f64::INFINITY,).map(|rec| rec.t,);
+			assert_eq!(tree.hit(&ray, 0.001, f64::INFINITY,).map(|rec| rec.t,), expected);
+			assert_eq!(linear.hit(&ray, 0.001, f64::INFINITY,).map(|rec| rec.t,), expected);
+			hits += expected.is_some() as usize;
+		}
+		// Both outcomes are covered.
+		assert!(hits > 100 && hits < 1900, "{} hits", hits);
+	}
+
+	#[test]
+	fn unbounded_objects_stay_beside_the_tree() {
+		let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5,),),);
+		let floor: Arc<dyn Hittable,> = Arc::new(Plane::new(
+			Point3::default(),
+			Vec3::new(0.0, 1.0, 0.0,),
+			material.clone(),
+		),);
+		let wall = HittableList {
+			objects: vec![Arc::new(Plane::new(
+				Point3::new(0.0, 0.0, -10.0,),
+				Vec3::new(0.0, 0.0, 1.0,),
+				material,
+			),)],
+		};
+		let mut objects = random_spheres(50, &mut Rng(7,),);
+		objects.push(floor,);
+		objects.push(Arc::new(wall,),);
+		let list = HittableList { objects, };
+
+		let accelerated = accelerate(&list, 0.0, 1.0,);
+		// The plane, the list holding the other one and a single tree over the spheres.
+		assert_eq!(accelerated.objects.len(), 3);
+		assert!(accelerated.objects[.. 2].iter().all(|o| o.bounding_box(0.0, 1.0,).is_none()));
+
+		let mut rng = Rng(11,);
+		for _ in 0 .. 500 {
+			let ray = Ray::new(rng.point(120.0,), rng.point(1.0,), 0.0,);
+			assert_eq!(
+				accelerated.hit(&ray, 0.001, f64::INFINITY,).map(|rec| rec.t,),
+				list.hit(&ray, 0.001, f64::INFINITY,).map(|rec| rec.t,)
+			);
+		}
+	}
 }
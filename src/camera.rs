@@ -1,6 +1,7 @@
 use crate::ray::Ray;
-use crate::util::{degrees_to_radians, random_f64_range};
-use crate::vec3::{cross, random_in_unit_disk, unit_vector, Point3, Vec3};
+use crate::sampler::Sampler;
+use crate::util::degrees_to_radians;
+use crate::vec3::{cross, sample_unit_disk, unit_vector, Point3, Vec3};
 
 pub struct Camera {
 	origin: Point3,
@@ -56,13 +57,13 @@ impl Camera {
 		}
 	}
 
-	pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-		let rd = self.lens_radius * random_in_unit_disk();
+	pub fn get_ray(&self, s: f64, t: f64, sampler: &mut dyn Sampler) -> Ray {
+		let rd = self.lens_radius * sample_unit_disk(sampler.get_2d());
 		let offset = self.u * rd.x() + self.v * rd.y();
 		Ray::new(
 			self.origin + offset,
 			self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
-			random_f64_range(self.time0, self.time1),
+			self.time0 + (self.time1 - self.time0) * sampler.get_1d(),
 		)
 	}
 }
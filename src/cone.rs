@@ -0,0 +1,145 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+	aabb::AABB,
+	disk::{Disk, disk_extent},
+	hittable::{HitRecord, Hittable},
+	material::Material,
+	onb::Onb,
+	ray::Ray,
+	vec3::{Point3, unit_vector, Vec3},
+};
+
+/// A cone with a circular base of `radius` around `base` and its apex at `base + axis`. UVs on
+/// the side run around the axis and up from the base, a capped cone closes the base with a
+/// `Disk`.
+pub struct Cone {
+	base:    Point3,
+	height:  f64,
+	radius:  f64,
+	mat_ptr: Arc<dyn Material,>,
+	onb:     Onb,
+	cap:     Option<Disk,>,
+}
+
+impl Cone {
+	pub fn new(
+		base: Point3,
+		axis: Vec3,
+		radius: f64,
+		capped: bool,
+		mat_ptr: Arc<dyn Material,>,
+	) -> Self {
+		let cap = if capped {
+			Some(Disk::new(base, -axis, radius, mat_ptr.clone(),),)
+		} else {
+			None
+		};
+
+		Cone {
+			base,
+			height: axis.length(),
+			radius,
+			mat_ptr,
+			onb: Onb::build_from_w(&axis,),
+			cap,
+		}
+	}
+
+	fn side_hit(&self, r: &Ray, t: f64, local: Point3,) -> HitRecord {
+		let slope = self.radius / self.height;
+		let gradient = Vec3::new(
+			local.x(),
+			local.y(),
+			slope * slope * (self.height - local.z()),
+		);
+		// The apex has no normal of its own, the axis is the most sensible direction there.
+		let outward_normal = if gradient.near_zero() {
+			self.onb.w()
+		} else {
+			unit_vector(&self.onb.local(&gradient,),)
+		};
+
+		let mut rec = HitRecord {
+			p: r.at(t,),
+			normal: Default::default(),
+			mat_ptr: self.mat_ptr.clone(),
+			t,
+			u: 0.5 + local.y().atan2(local.x(),) / (2.0 * PI),
+			v: local.z() / self.height,
+			front_face: false,
+		};
+		rec.set_face_normal(r, &outward_normal,);
+		rec
+	}
+}
+
+impl Hittable for Cone {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let o = self.onb.to_local(&(r.origin() - self.base),);
+		let d = self.onb.to_local(&r.direction(),);
+
+		// The side is where x² + y² = s²(h - z)², with s the radius per unit of height.
+		let s2 = (self.radius / self.height).powi(2,);
+		let q = self.height - o.z();
+		let a = d.x() * d.x() + d.y() * d.y() - s2 * d.z() * d.z();
+		let half_b = o.x() * d.x() + o.y() * d.y() + s2 * q * d.z();
+		let c = o.x() * o.x() + o.y() * o.y() - s2 * q * q;
+
+		let mut roots = [f64::NAN; 2];
+		if a.abs() < 1e-12 {
+			// Parallel to the slant of the cone, the quadratic degenerates into a line.
+			if half_b.abs() > 1e-12 {
+				roots[0] = -c / (2.0 * half_b);
+			}
+		} else {
+			let discriminant = half_b * half_b - a * c;
+			if discriminant >= 0.0 {
+				let sqrtd = discriminant.sqrt();
+				roots = [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a,];
+				if roots[0] > roots[1] {
+					roots.swap(0, 1,);
+				}
+			}
+		}
+
+		let mut closest = None;
+		for &t in &roots {
+			if t.is_nan() || t < t_min || t > t_max {
+				continue;
+			}
+
+			// Only the nappe between the base and the apex belongs to the cone.
+			let local = o + t * d;
+			if local.z() < 0.0 || local.z() > self.height {
+				continue;
+			}
+
+			closest = Some(self.side_hit(r, t, local,),);
+			break;
+		}
+
+		if let Some(cap,) = &self.cap {
+			let t_max = closest.as_ref().map_or(t_max, |rec: &HitRecord| rec.t,);
+			if let Some(rec,) = cap.hit(r, t_min, t_max,) {
+				closest = Some(rec,);
+			}
+		}
+
+		closest
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> {
+		let extent = disk_extent(&self.onb.w(), self.radius,);
+		let apex = self.base + self.height * self.onb.w();
+
+		let mut min = Vec3::default();
+		let mut max = Vec3::default();
+		for c in 0 .. 3 {
+			min[c] = (self.base[c] - extent[c]).min(apex[c],);
+			max[c] = (self.base[c] + extent[c]).max(apex[c],);
+		}
+
+		Some(AABB::new(min, max,),)
+	}
+}
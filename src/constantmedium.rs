@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::{
 	aabb::AABB,
@@ -11,24 +11,24 @@ use crate::{
 };
 
 pub struct ConstantMedium {
-	boundary:        Rc<dyn Hittable,>,
-	phase_function:  Rc<dyn Material,>,
+	boundary:        Arc<dyn Hittable,>,
+	phase_function:  Arc<dyn Material,>,
 	neg_inv_density: f64,
 }
 
 impl ConstantMedium {
-	pub fn from_texture(boundary: Rc<dyn Hittable,>, d: f64, a: Rc<dyn Texture,>,) -> Self {
+	pub fn from_texture(boundary: Arc<dyn Hittable,>, d: f64, a: Arc<dyn Texture,>,) -> Self {
 		ConstantMedium {
 			boundary,
-			phase_function: Rc::new(Isotrophic::new(a,),),
+			phase_function: Arc::new(Isotrophic::new(a,),),
 			neg_inv_density: -1.0 / d,
 		}
 	}
 
-	pub fn from_color(boundary: Rc<dyn Hittable,>, d: f64, a: Color,) -> Self {
+	pub fn from_color(boundary: Arc<dyn Hittable,>, d: f64, a: Color,) -> Self {
 		ConstantMedium {
 			boundary,
-			phase_function: Rc::new(Isotrophic::from(a,),),
+			phase_function: Arc::new(Isotrophic::from(a,),),
 			neg_inv_density: -1.0 / d,
 		}
 	}
@@ -0,0 +1,267 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::{AABB, surrounding_box},
+	hittable::{HitRecord, Hittable},
+	ray::Ray,
+	vec3::Vec3,
+};
+
+/// How far past a surface the search for the next one starts, so it is not found again.
+const EPSILON: f64 = 1e-7;
+
+#[derive(Clone, Copy,)]
+pub enum Operation {
+	Union,
+	Intersection,
+	/// Everything in the first object that is not in the second.
+	Difference,
+}
+
+impl Operation {
+	fn inside(self, in_a: bool, in_b: bool,) -> bool {
+		match self {
+			Operation::Union => in_a || in_b,
+			Operation::Intersection => in_a && in_b,
+			Operation::Difference => in_a && !in_b,
+		}
+	}
+}
+
+/// Combines two closed objects into one solid. Both children are walked from surface to surface
+/// along the ray, every surface takes the ray into or out of that child, and the first surface
+/// where the ray enters or leaves the combination is reported. Whether the ray starts inside a
+/// child is told by the number of its surfaces ahead, so children only need to be closed, like
+/// `Sphere`, `Box` and watertight meshes, and their normals may point either way.
+pub struct Csg {
+	a:         Arc<dyn Hittable,>,
+	b:         Arc<dyn Hittable,>,
+	operation: Operation,
+}
+
+impl Csg {
+	pub fn new(a: Arc<dyn Hittable,>, b: Arc<dyn Hittable,>, operation: Operation,) -> Self {
+		Csg { a, b, operation, }
+	}
+
+	pub fn union(a: Arc<dyn Hittable,>, b: Arc<dyn Hittable,>,) -> Self {
+		Csg::new(a, b, Operation::Union,)
+	}
+
+	pub fn intersection(a: Arc<dyn Hittable,>, b: Arc<dyn Hittable,>,) -> Self {
+		Csg::new(a, b, Operation::Intersection,)
+	}
+
+	pub fn difference(a: Arc<dyn Hittable,>, b: Arc<dyn Hittable,>,) -> Self {
+		Csg::new(a, b, Operation::Difference,)
+	}
+}
+
+/// Whether `r` starts inside the closed `object`, given its `first` surface along the ray. An
+/// odd number of surfaces ahead means the ray has to leave the object once more than it enters.
+fn starts_inside(object: &dyn Hittable, r: &Ray, first: Option<&HitRecord,>,) -> bool {
+	let mut inside = false;
+	let mut next = first.map(|rec| rec.t,);
+	while let Some(t,) = next {
+		inside = !inside;
+		next = object.hit(r, t + EPSILON, f64::INFINITY,).map(|rec| rec.t,);
+	}
+	inside
+}
+
+impl Hittable for Csg {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		// Surfaces beyond `t_max` still tell whether the ray starts inside a child.
+		let mut hit_a = self.a.hit(r, t_min, f64::INFINITY,);
+		let mut hit_b = self.b.hit(r, t_min, f64::INFINITY,);
+		let mut in_a = starts_inside(&*self.a, r, hit_a.as_ref(),);
+		let mut in_b = starts_inside(&*self.b, r, hit_b.as_ref(),);
+
+		loop {
+			let inside = self.operation.inside(in_a, in_b,);
+
+			let from_a = match (&hit_a, &hit_b,) {
+				(Some(a,), Some(b,),) => a.t <= b.t,
+				(Some(_,), None,) => true,
+				(None, Some(_,),) => false,
+				(None, None,) => return None,
+			};
+			let rec = if from_a {
+				in_a = !in_a;
+				hit_a.take()
+			} else {
+				in_b = !in_b;
+				hit_b.take()
+			}?;
+
+			if rec.t > t_max {
+				return None;
+			}
+
+			let entering = self.operation.inside(in_a, in_b,);
+			if entering != inside {
+				// The normal already faces the ray, only which side of the solid it is on can
+				// change, as on the inside of a subtracted object.
+				return Some(HitRecord {
+					front_face: entering,
+					..rec
+				},);
+			}
+
+			if from_a {
+				hit_a = self.a.hit(r, rec.t + EPSILON, f64::INFINITY,);
+			} else {
+				hit_b = self.b.hit(r, rec.t + EPSILON, f64::INFINITY,);
+			}
+		}
+	}
+
+	fn bounding_box(&self, time0: f64, time1: f64,) -> Option<AABB,> {
+		let box_a = self.a.bounding_box(time0, time1,);
+		let box_b = self.b.bounding_box(time0, time1,);
+
+		match self.operation {
+			Operation::Union => Some(surrounding_box(box_a?, box_b?,),),
+			Operation::Intersection => match (box_a, box_b,) {
+				(Some(a,), Some(b,),) => {
+					let mut min = Vec3::default();
+					let mut max = Vec3::default();
+					for c in 0 .. 3 {
+						min[c] = a.min()[c].max(b.min()[c],);
+						max[c] = a.max()[c].min(b.max()[c],).max(min[c],);
+					}
+					Some(AABB::new(min, max,),)
+				},
+				(a, b,) => a.or(b,),
+			},
+			Operation::Difference => box_a,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		_box::Box,
+		material::Lambertian,
+		sphere::Sphere,
+		vec3::{Color, Point3},
+	};
+
+	/// Two unit spheres around x = -0.5 and x = 0.5, they overlap between x = -0.5 and 0.5.
+	fn spheres() -> (Arc<dyn Hittable,>, Arc<dyn Hittable,>,) {
+		let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5,),),);
+		(
+			Arc::new(Sphere::new(
+				Vec3::new(-0.5, 0.0, 0.0,),
+				1.0,
+				material.clone(),
+			),),
+			Arc::new(Sphere::new(Vec3::new(0.5, 0.0, 0.0,), 1.0, material,),),
+		)
+	}
+
+	/// Where a ray along +x from `x` next crosses the surface of `csg`, and whether it enters.
+	fn next_surface(csg: &Csg, x: f64,) -> Option<(f64, bool,),> {
+		let ray = Ray::new(Point3::new(x, 0.0, 0.0,), Vec3::new(1.0, 0.0, 0.0,), 0.0,);
+		csg.hit(&ray, 0.001, f64::INFINITY,).map(|rec| {
+			// The reported normal always faces the ray.
+			assert!(rec.normal.x() < 0.0);
+			(rec.p.x(), rec.front_face,)
+		},)
+	}
+
+	fn assert_surface(surface: Option<(f64, bool,),>, x: f64, entering: bool,) {
+		let (found, found_entering,) = surface.expect("missed the surface",);
+		assert!(
+			(found - x).abs() < 1e-9,
+			"surface at {} instead of {}",
+			found,
+			x
+		);
+		assert_eq!(found_entering, entering);
+	}
+
+	#[test]
+	fn union_spans_both_children() {
+		let (a, b,) = spheres();
+		let csg = Csg::union(a, b,);
+		assert_surface(next_surface(&csg, -5.0,), -1.5, true,);
+		// Inside both, inside only one, and past the overlap.
+		assert_surface(next_surface(&csg, 0.0,), 1.5, false,);
+		assert_surface(next_surface(&csg, -1.0,), 1.5, false,);
+		assert_surface(next_surface(&csg, 1.0,), 1.5, false,);
+		assert!(next_surface(&csg, 2.0,).is_none());
+	}
+
+	#[test]
+	fn intersection_is_the_overlap() {
+		let (a, b,) = spheres();
+		let csg = Csg::intersection(a, b,);
+		assert_surface(next_surface(&csg, -5.0,), -0.5, true,);
+		assert_surface(next_surface(&csg, -1.0,), -0.5, true,);
+		assert_surface(next_surface(&csg, 0.0,), 0.5, false,);
+		assert!(next_surface(&csg, 1.0,).is_none());
+	}
+
+	#[test]
+	fn difference_removes_the_second_child() {
+		let (a, b,) = spheres();
+		let csg = Csg::difference(a, b,);
+		assert_surface(next_surface(&csg, -5.0,), -1.5, true,);
+		// Leaving through the surface of the subtracted sphere.
+		assert_surface(next_surface(&csg, -1.0,), -0.5, false,);
+		assert!(next_surface(&csg, 0.0,).is_none());
+	}
+
+	#[test]
+	fn disjoint_children_have_no_intersection() {
+		let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5,),),);
+		let a = Arc::new(Sphere::new(
+			Vec3::new(-2.0, 0.0, 0.0,),
+			1.0,
+			material.clone(),
+		),);
+		let b = Arc::new(Sphere::new(Vec3::new(2.0, 0.0, 0.0,), 1.0, material,),);
+		assert!(next_surface(&Csg::intersection(a.clone(), b.clone(),), -5.0,).is_none());
+		assert_surface(next_surface(&Csg::difference(a, b,), -1.5,), -1.0, false,);
+	}
+
+	#[test]
+	fn surfaces_past_t_max_are_missed() {
+		let (a, b,) = spheres();
+		let csg = Csg::intersection(a, b,);
+		let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0,), Vec3::new(1.0, 0.0, 0.0,), 0.0,);
+		assert!(csg.hit(&ray, 0.001, 4.0,).is_none());
+		assert!(csg.hit(&ray, 0.001, 5.0,).is_some());
+	}
+
+	#[test]
+	fn boxes_with_inward_facing_sides_work() {
+		// The sides of `Box` at its minimum corner face inwards, a ray leaving through them
+		// still counts as leaving.
+		let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5,),),);
+		let cube = Arc::new(Box::new(
+			&Point3::new(-1.0, -1.0, -1.0,),
+			&Point3::new(1.0, 1.0, 1.0,),
+			material.clone(),
+		),);
+		let hole = Arc::new(Sphere::new(Vec3::default(), 0.5, material,),);
+		let csg = Csg::difference(cube, hole,);
+
+		for &(x, direction, surface, entering,) in &[
+			(-5.0, 1.0, -1.0, true,),
+			(0.75, -1.0, 0.5, false,),
+			(-0.75, 1.0, -0.5, false,),
+			(0.0, -1.0, -0.5, true,),
+			(0.75, 1.0, 1.0, false,),
+			(-0.75, -1.0, -1.0, false,),
+		] {
+			let ray = Ray::new(Point3::new(x, 0.0, 0.0,), Vec3::new(direction, 0.0, 0.0,), 0.0,);
+			let rec = csg.hit(&ray, 0.001, f64::INFINITY,).expect("missed the surface",);
+			assert!((rec.p.x() - surface).abs() < 1e-9, "{} from {}", rec.p.x(), x);
+			assert_eq!(rec.front_face, entering, "from {} along {}", x, direction);
+		}
+	}
+}
@@ -0,0 +1,118 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+	aabb::AABB,
+	disk::{Disk, disk_extent},
+	hittable::{HitRecord, Hittable},
+	material::Material,
+	onb::Onb,
+	ray::Ray,
+	vec3::{Point3, Vec3},
+};
+
+/// A cylinder standing on `base` and reaching along `axis`, whose length is the height. UVs on
+/// the side run around the axis and up from the base, capped cylinders close both ends with
+/// `Disk`s.
+pub struct Cylinder {
+	base:    Point3,
+	height:  f64,
+	radius:  f64,
+	mat_ptr: Arc<dyn Material,>,
+	onb:     Onb,
+	caps:    Option<[Disk; 2],>,
+}
+
+impl Cylinder {
+	pub fn new(
+		base: Point3,
+		axis: Vec3,
+		radius: f64,
+		capped: bool,
+		mat_ptr: Arc<dyn Material,>,
+	) -> Self {
+		let caps = if capped {
+			Some([
+				Disk::new(base, -axis, radius, mat_ptr.clone(),),
+				Disk::new(base + axis, axis, radius, mat_ptr.clone(),),
+			],)
+		} else {
+			None
+		};
+
+		Cylinder {
+			base,
+			height: axis.length(),
+			radius,
+			mat_ptr,
+			onb: Onb::build_from_w(&axis,),
+			caps,
+		}
+	}
+}
+
+impl Hittable for Cylinder {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let o = self.onb.to_local(&(r.origin() - self.base),);
+		let d = self.onb.to_local(&r.direction(),);
+
+		let mut closest = None;
+		let a = d.x() * d.x() + d.y() * d.y();
+		if a > 1e-12 {
+			let half_b = o.x() * d.x() + o.y() * d.y();
+			let c = o.x() * o.x() + o.y() * o.y() - self.radius * self.radius;
+			let discriminant = half_b * half_b - a * c;
+
+			if discriminant >= 0.0 {
+				let sqrtd = discriminant.sqrt();
+				for &t in &[(-half_b - sqrtd) / a, (-half_b + sqrtd) / a,] {
+					let z = o.z() + t * d.z();
+					if t < t_min || t > t_max || z < 0.0 || z > self.height {
+						continue;
+					}
+
+					let local = o + t * d;
+					let mut rec = HitRecord {
+						p: r.at(t,),
+						normal: Default::default(),
+						mat_ptr: self.mat_ptr.clone(),
+						t,
+						u: 0.5 + local.y().atan2(local.x(),) / (2.0 * PI),
+						v: z / self.height,
+						front_face: false,
+					};
+					let outward_normal =
+						self.onb.local(&Vec3::new(local.x(), local.y(), 0.0,),) / self.radius;
+					rec.set_face_normal(r, &outward_normal,);
+
+					closest = Some(rec,);
+					break;
+				}
+			}
+		}
+
+		if let Some(caps,) = &self.caps {
+			for cap in caps {
+				let t_max = closest.as_ref().map_or(t_max, |rec: &HitRecord| rec.t,);
+				if let Some(rec,) = cap.hit(r, t_min, t_max,) {
+					closest = Some(rec,);
+				}
+			}
+		}
+
+		closest
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> {
+		let extent = disk_extent(&self.onb.w(), self.radius,);
+		let top = self.base + self.height * self.onb.w();
+
+		let mut min = Vec3::default();
+		let mut max = Vec3::default();
+		for c in 0 .. 3 {
+			min[c] = self.base[c].min(top[c],) - extent[c];
+			max[c] = self.base[c].max(top[c],) + extent[c];
+		}
+
+		Some(AABB::new(min, max,),)
+	}
+}
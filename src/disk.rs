@@ -0,0 +1,80 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+	aabb::AABB,
+	hittable::{HitRecord, Hittable},
+	material::Material,
+	onb::Onb,
+	ray::Ray,
+	vec3::{dot, Point3, Vec3},
+};
+
+/// A flat disk facing along `normal`. The `u` coordinate runs around the center and `v` from the
+/// center out to the rim.
+pub struct Disk {
+	center:  Point3,
+	radius:  f64,
+	mat_ptr: Arc<dyn Material,>,
+	onb:     Onb,
+}
+
+impl Disk {
+	pub fn new(center: Point3, normal: Vec3, radius: f64, mat_ptr: Arc<dyn Material,>,) -> Self {
+		Disk {
+			center,
+			radius,
+			mat_ptr,
+			onb: Onb::build_from_w(&normal,),
+		}
+	}
+}
+
+impl Hittable for Disk {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let normal = self.onb.w();
+		let denom = dot(&normal, &r.direction(),);
+		if denom.abs() < 1e-8 {
+			return None;
+		}
+
+		let t = dot(&normal, &(self.center - r.origin()),) / denom;
+		if t < t_min || t > t_max {
+			return None;
+		}
+
+		let p = r.at(t,);
+		let local = self.onb.to_local(&(p - self.center),);
+		let distance = (local.x() * local.x() + local.y() * local.y()).sqrt();
+		if distance > self.radius {
+			return None;
+		}
+
+		let mut rec = HitRecord {
+			p,
+			normal: Default::default(),
+			mat_ptr: self.mat_ptr.clone(),
+			t,
+			u: 0.5 + local.y().atan2(local.x(),) / (2.0 * PI),
+			v: distance / self.radius,
+			front_face: false,
+		};
+		rec.set_face_normal(r, &normal,);
+
+		Some(rec,)
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> {
+		let extent = disk_extent(&self.onb.w(), self.radius,);
+		Some(AABB::new(self.center - extent, self.center + extent,),)
+	}
+}
+
+/// How far a circle of `radius` around the unit `axis` reaches along each coordinate axis. Flat
+/// axes get a little padding so that boxes never have zero thickness.
+pub fn disk_extent(axis: &Vec3, radius: f64,) -> Vec3 {
+	let mut extent = Vec3::default();
+	for c in 0 .. 3 {
+		extent[c] = (radius * (1.0 - axis[c] * axis[c]).max(0.0,).sqrt()).max(0.0001,);
+	}
+	extent
+}
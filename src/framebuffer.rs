@@ -0,0 +1,111 @@
+use std::{
+	fs::File,
+	io::{BufWriter, Write},
+	path::Path,
+};
+
+use image::{
+	codecs::hdr::HdrEncoder, save_buffer_with_format, ColorType, ImageFormat, ImageResult, Rgb,
+};
+
+use crate::{color::to_rgb8, vec3::Color};
+
+/// The image formats that can be saved: PNG, JPEG, binary PPM or BMP, or unclamped linear
+/// radiance as Radiance HDR or PFM.
+#[derive(Clone, Copy,)]
+pub enum OutputFormat {
+	Png,
+	Jpeg,
+	Ppm,
+	Bmp,
+	Hdr,
+	Pfm,
+}
+
+impl OutputFormat {
+	/// The format given by the extension of `path`.
+	pub fn from_path(path: &Path,) -> Result<Self, String,> {
+		let extension = path
+			.extension()
+			.and_then(|e| e.to_str(),)
+			.unwrap_or_default();
+		match extension.to_ascii_lowercase().as_str() {
+			"png" => Ok(OutputFormat::Png,),
+			"jpg" | "jpeg" => Ok(OutputFormat::Jpeg,),
+			"ppm" => Ok(OutputFormat::Ppm,),
+			"bmp" => Ok(OutputFormat::Bmp,),
+			"hdr" => Ok(OutputFormat::Hdr,),
+			"pfm" => Ok(OutputFormat::Pfm,),
+			"" => Err("no extension to pick the output format from".to_string(),),
+			_ => Err(format!("unsupported output format `{}`", extension),),
+		}
+	}
+}
+
+/// The averaged linear radiance of every pixel of a rendered image, top row first.
+pub struct FrameBuffer {
+	pub width:  usize,
+	pub height: usize,
+	pub pixels: Vec<Color,>,
+}
+
+impl FrameBuffer {
+	pub fn new(width: usize, height: usize,) -> Self {
+		FrameBuffer {
+			width,
+			height,
+			pixels: vec![Color::default(); width * height],
+		}
+	}
+
+	pub fn to_rgb8(&self,) -> Vec<u8,> { self.pixels.iter().flat_map(to_rgb8,).collect() }
+
+	/// Saves the image to `path` as `format`, see `OutputFormat::from_path`.
+	pub fn save(&self, path: &Path, format: OutputFormat,) -> ImageResult<(),> {
+		let format = match format {
+			OutputFormat::Png => ImageFormat::Png,
+			OutputFormat::Jpeg => ImageFormat::Jpeg,
+			OutputFormat::Ppm => ImageFormat::Pnm,
+			OutputFormat::Bmp => ImageFormat::Bmp,
+			OutputFormat::Hdr => return self.save_hdr(path,),
+			OutputFormat::Pfm => return self.save_pfm(path,),
+		};
+
+		save_buffer_with_format(
+			path,
+			&self.to_rgb8(),
+			self.width as u32,
+			self.height as u32,
+			ColorType::Rgb8,
+			format,
+		)
+	}
+
+	/// Writes Radiance RGBE, which keeps the full dynamic range at 8 bits of mantissa.
+	fn save_hdr(&self, path: &Path,) -> ImageResult<(),> {
+		let data: Vec<Rgb<f32,>,> = self
+			.pixels
+			.iter()
+			.map(|c| Rgb([c.x() as f32, c.y() as f32, c.z() as f32,],),)
+			.collect();
+
+		HdrEncoder::new(BufWriter::new(File::create(path,)?,),).encode(&data, self.width, self.height,)
+	}
+
+	/// Writes a little endian Portable Float Map, which stores its rows bottom to top.
+	fn save_pfm(&self, path: &Path,) -> ImageResult<(),> {
+		let mut file = BufWriter::new(File::create(path,)?,);
+		write!(file, "PF\n{} {}\n-1.0\n", self.width, self.height)?;
+
+		for row in self.pixels.chunks(self.width,).rev() {
+			for c in row {
+				for component in &c.e {
+					file.write_all(&(*component as f32).to_le_bytes(),)?;
+				}
+			}
+		}
+
+		file.flush()?;
+		Ok((),)
+	}
+}
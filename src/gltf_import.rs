@@ -0,0 +1,370 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use gltf::{
+	camera::Projection,
+	image::{Data, Format},
+	material::AlphaMode,
+	mesh::Mode,
+	scene::Transform,
+	Node,
+};
+
+use crate::{
+	hittable_list::HittableList,
+	material::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
+	matrix::Mat4,
+	mesh::TriangleMesh,
+	quat::Quat,
+	scenes::Scene,
+	texture::ImageTexture,
+	vec3::{unit_vector, Color, Point3, Vec3},
+};
+
+/// Converts decoded image data to the three bytes per pixel `ImageTexture` works with, scaling
+/// every channel by `factor`. Only the most significant byte of 16-bit channels is kept.
+fn to_rgb8(image: &Data, factor: &Color,) -> Vec<u8,> {
+	let (channels, bytes,) = match image.format {
+		Format::R8 => (1, 1,),
+		Format::R8G8 => (2, 1,),
+		Format::R8G8B8 | Format::B8G8R8 => (3, 1,),
+		Format::R8G8B8A8 | Format::B8G8R8A8 => (4, 1,),
+		Format::R16 => (1, 2,),
+		Format::R16G16 => (2, 2,),
+		Format::R16G16B16 => (3, 2,),
+		Format::R16G16B16A16 => (4, 2,),
+	};
+	let bgr = matches!(image.format, Format::B8G8R8 | Format::B8G8R8A8);
+
+	image
+		.pixels
+		.chunks_exact(channels * bytes,)
+		.flat_map(|pixel| {
+			let channel = |c: usize| pixel[c * bytes + bytes - 1] as f64;
+			let rgb = match channels {
+				1 | 2 => [channel(0,); 3],
+				_ if bgr => [channel(2,), channel(1,), channel(0,),],
+				_ => [channel(0,), channel(1,), channel(2,),],
+			};
+			(0 .. 3).map(move |c| (rgb[c] * factor[c]).clamp(0.0, 255.0,) as u8,)
+		},)
+		.collect()
+}
+
+fn vec3(v: &[f32],) -> Vec3 { Vec3::new(v[0] as f64, v[1] as f64, v[2] as f64,) }
+
+/// Maps a metallic-roughness material onto the closest material we have: emissive materials
+/// become lights, transmissive or blended transparent ones glass, mostly metallic ones `Metal`
+/// with the roughness as fuzz, and everything else a `Lambertian` with the base color texture.
+fn to_material(material: &gltf::Material, images: &[Data],) -> Arc<dyn Material,> {
+	let pbr = material.pbr_metallic_roughness();
+	let base_color = pbr.base_color_factor();
+
+	let emissive = vec3(&material.emissive_factor(),);
+	if !emissive.near_zero() {
+		return Arc::new(DiffuseLight::from(emissive,),);
+	}
+
+	let transmission = material.transmission().map_or(0.0, |t| t.transmission_factor(),);
+	if transmission > 0.0 || (material.alpha_mode() == AlphaMode::Blend && base_color[3] < 1.0) {
+		return Arc::new(Dielectric::new(material.ior().unwrap_or(1.5,) as f64,),);
+	}
+
+	if pbr.metallic_factor() >= 0.5 {
+		return Arc::new(Metal::new(vec3(&base_color,), pbr.roughness_factor() as f64,),);
+	}
+
+	match pbr.base_color_texture() {
+		Some(info,) => {
+			let image = &images[info.texture().source().index()];
+			let data = to_rgb8(image, &vec3(&base_color,),);
+			let texture = ImageTexture::from_rgb8(image.width as usize, image.height as usize, data,);
+			Arc::new(Lambertian::from(Arc::new(texture,),),)
+		},
+		None => Arc::new(Lambertian::new(vec3(&base_color,),),),
+	}
+}
+
+struct Importer<'a> {
+	buffers:   &'a [gltf::buffer::Data],
+	images:    &'a [Data],
+	materials: HashMap<Option<usize,>, Arc<dyn Material,>,>,
+	world:     HittableList,
+	camera:    Option<(Mat4, gltf::Camera<'a,>,),>,
+	emissive:  bool,
+}
+
+impl<'a> Importer<'a> {
+	fn material(&mut self, material: &gltf::Material,) -> Arc<dyn Material,> {
+		if material.emissive_factor().iter().any(|&c| c > 0.0,) {
+			self.emissive = true;
+		}
+
+		let images = self.images;
+		self.materials
+			.entry(material.index(),)
+			.or_insert_with(|| to_material(material, images,),)
+			.clone()
+	}
+
+	/// Adds the triangle primitives of `mesh` with `transform` baked in. A transform that cannot
+	/// be inverted skips the mesh, as its normals cannot be transformed.
+	fn mesh(&mut self, mesh: &gltf::Mesh<'a,>, transform: &Mat4,) {
+		let linear = transform.linear();
+		let normal_matrix = match linear.inverse() {
+			Some(inverse,) => inverse.transpose(),
+			None => {
+				eprintln!("skipping mesh {}, its transform is not invertible", mesh.index());
+				return;
+			},
+		};
+		// A mirroring transform turns counter-clockwise triangles clockwise.
+		let mirrored = linear.determinant() < 0.0;
+
+		for primitive in mesh.primitives() {
+			if primitive.mode() != Mode::Triangles {
+				eprintln!(
+					"skipping primitive {} of mesh {}, only triangles are supported",
+					primitive.index(),
+					mesh.index()
+				);
+				continue;
+			}
+
+			let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()],),);
+			let positions: Vec<Point3,> = match reader.read_positions() {
+				Some(positions,) => positions
+					.map(|p| transform.transform_point(&vec3(&p,),),)
+					.collect(),
+				None => continue,
+			};
+			let normals = reader.read_normals().map(|normals| {
+				normals
+					.map(|n| unit_vector(&(normal_matrix * vec3(&n,)),),)
+					.collect()
+			},);
+			// glTF puts the texture origin in the top left corner, we put it in the bottom left.
+			let uvs = reader.read_tex_coords(0,).map(|uvs| {
+				uvs.into_f32().map(|[u, v,]| (u as f64, 1.0 - v as f64,),).collect()
+			},);
+			let flat: Vec<u32,> = match reader.read_indices() {
+				Some(indices,) => indices.into_u32().collect(),
+				None => (0 .. positions.len() as u32).collect(),
+			};
+			let indices = flat
+				.chunks_exact(3,)
+				.map(|t| if mirrored { [t[0], t[2], t[1],] } else { [t[0], t[1], t[2],] },)
+				.collect();
+
+			let mat_ptr = self.material(&primitive.material(),);
+			self.world.add(Arc::new(TriangleMesh::new(positions, normals, uvs, indices, mat_ptr,),),);
+		}
+	}
+
+	/// Adds the meshes of `node` and its children with their world transforms baked in.
+	fn node(&mut self, node: &Node<'a,>, parent: &Mat4,) {
+		let local = match node.transform() {
+			// glTF stores matrices column by column.
+			Transform::Matrix { matrix, } => {
+				Mat4::new(matrix.map(|column| column.map(|value| value as f64,),),).transpose()
+			},
+			Transform::Decomposed {
+				translation,
+				rotation: [x, y, z, w,],
+				scale,
+			} => {
+				let rotation = Quat::new(w as f64, vec3(&[x, y, z,],),);
+				Mat4::translation(vec3(&translation,),)
+					* Mat4::from(rotation,)
+					* Mat4::scaling(vec3(&scale,),)
+			},
+		};
+		let transform = *parent * local;
+
+		if let Some(camera,) = node.camera() {
+			if self.camera.is_none() && matches!(camera.projection(), Projection::Perspective(_)) {
+				self.camera = Some((transform, camera,),);
+			}
+		}
+
+		if let Some(mesh,) = node.mesh() {
+			self.mesh(&mesh, &transform,);
+		}
+
+		for child in node.children() {
+			self.node(&child, &transform,);
+		}
+	}
+}
+
+/// Imports the default scene of a `.gltf` or `.glb` file. Node transforms are baked into the
+/// meshes and the first perspective camera found becomes the scene camera. Without emissive
+/// materials the scene is lit by a sky background, as glTF lights are not supported.
+pub fn load(path: &Path,) -> Result<Scene, gltf::Error,> {
+	let (document, buffers, images,) = gltf::import(path,)?;
+
+	let mut importer = Importer {
+		buffers:   &buffers,
+		images:    &images,
+		materials: HashMap::new(),
+		world:     HittableList::default(),
+		camera:    None,
+		emissive:  false,
+	};
+
+	if let Some(scene,) = document.default_scene().or_else(|| document.scenes().next(),) {
+		for node in scene.nodes() {
+			importer.node(&node, &Mat4::identity(),);
+		}
+	}
+
+	let mut scene = Scene {
+		world: importer.world,
+		..Default::default()
+	};
+
+	if !importer.emissive {
+		scene.background = Color::new(0.70, 0.80, 1.00,);
+	}
+
+	if let Some((transform, camera,),) = importer.camera {
+		let perspective = match camera.projection() {
+			Projection::Perspective(perspective,) => perspective,
+			Projection::Orthographic(_,) => unreachable!(),
+		};
+		scene.lookfrom = transform.transform_point(&Point3::default(),);
+		scene.lookat = scene.lookfrom + transform.transform_vector(&Vec3::new(0.0, 0.0, -1.0,),);
+		scene.vup = transform.transform_vector(&Vec3::new(0.0, 1.0, 0.0,),);
+		scene.vfov = perspective.yfov().to_degrees() as f64;
+		scene.focus_dist = 1.0;
+		if let Some(aspect_ratio,) = perspective.aspect_ratio() {
+			scene.aspect_ratio = aspect_ratio as f64;
+		}
+	}
+
+	Ok(scene,)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{hittable::Hittable, ray::Ray, util::temp_file};
+
+	/// A scene with a single triangle mesh, (0, 0, 0), (1, 0, 0) and (0, 1, 0) counter-clockwise
+	/// seen from +z, and a perspective camera, placed by `nodes`. Node 0 is the only root.
+	fn load_nodes(name: &str, nodes: &str,) -> Scene {
+		let gltf = format!(
+			r#"{{
+	"asset": {{ "version": "2.0" }},
+	"scene": 0,
+	"scenes": [{{ "nodes": [0] }}],
+	"nodes": {},
+	"meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }} }}] }}],
+	"cameras": [{{
+		"type": "perspective",
+		"perspective": {{ "yfov": 0.5, "znear": 0.1, "aspectRatio": 2.0 }}
+	}}],
+	"accessors": [{{
+		"bufferView": 0,
+		"componentType": 5126,
+		"count": 3,
+		"type": "VEC3",
+		"min": [0, 0, 0],
+		"max": [1, 1, 0]
+	}}],
+	"bufferViews": [{{ "buffer": 0, "byteLength": 36 }}],
+	"buffers": [{{
+		"byteLength": 36,
+		"uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+	}}]
+}}"#,
+			nodes
+		);
+		let path = temp_file(name, gltf.as_bytes(),);
+		let scene = load(&path,).unwrap();
+		std::fs::remove_file(path,).unwrap();
+		scene
+	}
+
+	fn assert_close(a: Vec3, b: Vec3,) {
+		assert!((a - b).length() < 1e-6, "{:?} != {:?}", a.e, b.e);
+	}
+
+	#[test]
+	fn applies_scale_then_rotation_then_translation() {
+		// A quarter turn around z.
+		let scene = load_nodes(
+			"trs.gltf",
+			r#"[{
+				"mesh": 0,
+				"translation": [1, 2, 3],
+				"rotation": [0, 0, 0.7071067811865476, 0.7071067811865476],
+				"scale": [2, 1, 1]
+			}]"#,
+		);
+
+		// The corners end up at (1, 2, 3), (1, 4, 3) and (0, 2, 3).
+		let aabb = scene.world.bounding_box(0.0, 1.0,).unwrap();
+		assert_close(Vec3::new(aabb.min().x(), aabb.min().y(), 3.0,), Point3::new(0.0, 2.0, 3.0,),);
+		assert_close(Vec3::new(aabb.max().x(), aabb.max().y(), 3.0,), Point3::new(1.0, 4.0, 3.0,),);
+	}
+
+	#[test]
+	fn bakes_parent_matrices_into_children() {
+		// The parent is a column-major translation by (0, 0, -2), the child doubles the size.
+		let scene = load_nodes(
+			"matrix.gltf",
+			r#"[
+				{ "children": [1], "matrix": [1,0,0,0, 0,1,0,0, 0,0,1,0, 0,0,-2,1] },
+				{ "mesh": 0, "scale": [2, 2, 2] }
+			]"#,
+		);
+
+		let aabb = scene.world.bounding_box(0.0, 1.0,).unwrap();
+		assert_close(Vec3::new(aabb.max().x(), aabb.max().y(), 0.0,), Vec3::new(2.0, 2.0, 0.0,),);
+		let z = 0.5 * (aabb.min().z() + aabb.max().z());
+		assert!((z + 2.0).abs() < 1e-6, "{}", z);
+	}
+
+	#[test]
+	fn mirrored_meshes_keep_facing_out() {
+		for (name, scale,) in &[("plain.gltf", "[1, 1, 1]",), ("mirrored.gltf", "[-1, 1, 1]",),] {
+			let scene = load_nodes(name, &format!(r#"[{{ "mesh": 0, "scale": {} }}]"#, scale),);
+			let x = if scale.starts_with("[-",) { -0.25 } else { 0.25 };
+			let ray = Ray::new(Point3::new(x, 0.25, 5.0,), Vec3::new(0.0, 0.0, -1.0,), 0.0,);
+			let rec = scene.world.hit(&ray, 0.001, f64::INFINITY,).unwrap();
+			assert!(rec.front_face, "{}", name);
+		}
+	}
+
+	#[test]
+	fn maps_the_camera() {
+		let scene = load_nodes(
+			"camera.gltf",
+			r#"[
+				{ "children": [1], "translation": [0, 1, 0] },
+				{ "camera": 0, "translation": [0, 0, 5], "rotation": [0, 1, 0, 0] }
+			]"#,
+		);
+
+		// Turned half way around y, the camera looks down +z instead of -z.
+		assert_close(scene.lookfrom, Point3::new(0.0, 1.0, 5.0,),);
+		assert_close(scene.lookat, Point3::new(0.0, 1.0, 6.0,),);
+		assert_close(scene.vup, Vec3::new(0.0, 1.0, 0.0,),);
+		assert!((scene.vfov - 0.5f64.to_degrees()).abs() < 1e-4, "{}", scene.vfov);
+		assert_eq!(scene.aspect_ratio, 2.0);
+	}
+
+	#[test]
+	fn singular_mesh_transforms_keep_their_children() {
+		let scene = load_nodes(
+			"singular.gltf",
+			r#"[
+				{ "mesh": 0, "children": [1], "scale": [0, 1, 1], "translation": [0, 3, 0] },
+				{ "camera": 0 }
+			]"#,
+		);
+
+		assert!(scene.world.objects.is_empty());
+		assert_close(scene.lookfrom, Point3::new(0.0, 3.0, 0.0,),);
+	}
+}
@@ -1,17 +1,18 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::{
 	aabb::AABB,
 	material::Material,
+	matrix::Mat4,
 	ray::Ray,
-	util::degrees_to_radians,
-	vec3::{dot, Point3, Vec3},
+	sampler::Sampler,
+	vec3::{dot, unit_vector, Point3, Vec3},
 };
 
 pub struct HitRecord {
 	pub p:          Point3,
 	pub normal:     Vec3,
-	pub mat_ptr:    Rc<dyn Material,>,
+	pub mat_ptr:    Arc<dyn Material,>,
 	pub t:          f64,
 	pub u:          f64,
 	pub v:          f64,
@@ -30,126 +31,108 @@ impl HitRecord {
 	}
 }
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
 	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,>;
 
 	fn bounding_box(&self, time0: f64, time1: f64,) -> Option<AABB,>;
-}
-
-pub struct Translate {
-	ptr:    Rc<dyn Hittable,>,
-	offset: Vec3,
-}
 
-impl Translate {
-	pub fn new(ptr: Rc<dyn Hittable,>, offset: Vec3,) -> Self { Translate { ptr, offset, } }
-}
+	/// The density, per unit solid angle, with which `random` picks `direction` from `origin`.
+	/// Objects that cannot be sampled as lights leave it at zero.
+	fn pdf_value(&self, _origin: &Point3, _direction: &Vec3,) -> f64 { 0.0 }
 
-impl Hittable for Translate {
-	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
-		let moved_r = Ray::new(r.origin() - self.offset, r.direction(), r.time(),);
-		self.ptr.hit(&moved_r, t_min, t_max,).map(|mut rec| {
-			rec.p += self.offset;
-			rec.set_face_normal(&moved_r, &rec.normal.clone(),);
-			rec
-		},)
-	}
-
-	fn bounding_box(&self, time0: f64, time1: f64,) -> Option<AABB,> {
-		self.ptr.bounding_box(time0, time1,).map(|mut b| {
-			b.minimum += self.offset;
-			b.maximum += self.offset;
-			b
-		},)
+	/// A direction from `origin` towards a random point on the object.
+	fn random(&self, _origin: &Point3, _sampler: &mut dyn Sampler,) -> Vec3 {
+		Vec3::new(1.0, 0.0, 0.0,)
 	}
 }
 
-pub struct RotateY {
-	ptr:       Rc<dyn Hittable,>,
-	sin_theta: f64,
-	cos_theta: f64,
-	bbox:      Option<AABB,>,
+/// The density of `direction` for a flat object of `area` that is sampled uniformly over its
+/// surface, converted from area to the solid angle seen from `origin`.
+pub fn area_pdf_value(object: &dyn Hittable, area: f64, origin: &Point3, direction: &Vec3,) -> f64 {
+	let rec = match object.hit(&Ray::new(*origin, *direction, 0.0,), 0.001, f64::INFINITY,) {
+		Some(rec,) => rec,
+		None => return 0.0,
+	};
+
+	let distance_squared = rec.t * rec.t * direction.length_squared();
+	let cosine = (dot(direction, &rec.normal,) / direction.length()).abs();
+	distance_squared / (cosine * area)
 }
 
-impl RotateY {
-	pub fn new(ptr: Rc<dyn Hittable,>, angle: f64,) -> Self {
-		let radians = degrees_to_radians(angle,);
-		let sin_theta = radians.sin();
-		let cos_theta = radians.cos();
-
-		match ptr.bounding_box(0.0, 1.0,) {
-			None => RotateY {
-				ptr,
-				sin_theta,
-				cos_theta,
-				bbox: None,
-			},
-			Some(mut bbox,) => {
-				let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY,);
-				let mut max = Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY,);
-
-				for i in 0 .. 2 {
-					for j in 0 .. 2 {
-						for k in 0 .. 2 {
-							let x = i as f64 * bbox.max().x() + (1 - i) as f64 * bbox.min().x();
-							let y = j as f64 * bbox.max().y() + (1 - j) as f64 * bbox.min().y();
-							let z = k as f64 * bbox.max().z() + (1 - k) as f64 * bbox.min().z();
-
-							let newx = cos_theta * x + sin_theta * z;
-							let newz = -sin_theta * x + cos_theta * z;
-
-							let tester = Vec3::new(newx, y, newz,);
-
-							for c in 0 .. 3 {
-								min[c] = min[c].min(tester[c],);
-								max[c] = max[c].max(tester[c],);
-							}
-						}
-					}
-				}
+/// Places an object in the world with an affine matrix. Rays are taken into object space with
+/// the inverse, hit points and normals are brought back with the matrix.
+pub struct Transform {
+	ptr:     Arc<dyn Hittable,>,
+	matrix:  Mat4,
+	inverse: Mat4,
+}
 
-				bbox = AABB::new(min, max,);
+impl Transform {
+	/// Panics if `matrix` cannot be inverted.
+	pub fn new(ptr: Arc<dyn Hittable,>, matrix: Mat4,) -> Self {
+		let inverse = matrix.inverse().expect("Transform needs an invertible matrix",);
 
-				RotateY {
-					ptr,
-					sin_theta,
-					cos_theta,
-					bbox: Some(bbox,),
-				}
-			},
+		Transform {
+			ptr,
+			matrix,
+			inverse,
 		}
 	}
-}
 
-impl Hittable for RotateY {
-	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
-		let mut origin = r.origin();
-		let mut direction = r.direction();
+	pub fn translate(ptr: Arc<dyn Hittable,>, offset: Vec3,) -> Self {
+		Transform::new(ptr, Mat4::translation(offset,),)
+	}
 
-		origin[0] = self.cos_theta * r.origin()[0] - self.sin_theta * r.origin()[2];
-		origin[2] = self.sin_theta * r.direction()[0] + self.cos_theta * r.origin()[2];
+	pub fn rotate(ptr: Arc<dyn Hittable,>, axis: Vec3, angle: f64,) -> Self {
+		Transform::new(ptr, Mat4::rotation(axis, angle,),)
+	}
 
-		direction[0] = self.cos_theta * r.direction()[0] - self.sin_theta * r.direction()[2];
-		direction[2] = self.sin_theta * r.direction()[0] + self.cos_theta * r.direction()[2];
+	pub fn rotate_y(ptr: Arc<dyn Hittable,>, angle: f64,) -> Self {
+		Transform::rotate(ptr, Vec3::new(0.0, 1.0, 0.0,), angle,)
+	}
+}
 
-		let rotated_r = Ray::new(origin, direction, r.time(),);
+impl Hittable for Transform {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		// The direction is not normalized, so `t` means the same in both spaces.
+		let object_r = Ray::new(
+			self.inverse.transform_point(&r.origin(),),
+			self.inverse.transform_vector(&r.direction(),),
+			r.time(),
+		);
+
+		self.ptr.hit(&object_r, t_min, t_max,).map(|mut rec| {
+			// The inverse transpose keeps the sign of the normal's dot product with the ray, so
+			// `front_face` stays valid and only the direction needs fixing up.
+			rec.p = self.matrix.transform_point(&rec.p,);
+			if let Some(normal,) = self.matrix.transform_normal(&rec.normal,) {
+				rec.normal = unit_vector(&normal,);
+			}
+			rec
+		},)
+	}
 
-		self.ptr.hit(&rotated_r, t_min, t_max,).map(|mut rec| {
-			let mut p = rec.p;
-			let mut normal = rec.normal;
+	fn bounding_box(&self, time0: f64, time1: f64,) -> Option<AABB,> {
+		let bbox = self.ptr.bounding_box(time0, time1,)?;
 
-			p[0] = self.cos_theta * rec.p[0] + self.sin_theta * rec.p[2];
-			p[2] = -self.sin_theta * rec.p[0] + self.cos_theta * rec.p[2];
+		let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY,);
+		let mut max = Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY,);
 
-			normal[0] = self.cos_theta * rec.normal[0] + self.sin_theta * rec.normal[2];
-			normal[2] = -self.sin_theta * rec.normal[0] + self.cos_theta * rec.normal[2];
+		for corner in 0 .. 8 {
+			let mut p = bbox.min();
+			for c in 0 .. 3 {
+				if corner & (1 << c) != 0 {
+					p[c] = bbox.max()[c];
+				}
+			}
 
-			rec.p = p;
-			rec.set_face_normal(&rotated_r, &normal,);
+			let p = self.matrix.transform_point(&p,);
+			for c in 0 .. 3 {
+				min[c] = min[c].min(p[c],);
+				max[c] = max[c].max(p[c],);
+			}
+		}
 
-			rec
-		},)
+		Some(AABB::new(min, max,),)
 	}
-
-	fn bounding_box(&self, time0: f64, time1: f64,) -> Option<AABB,> { self.bbox.clone() }
 }
@@ -1,15 +1,17 @@
 use crate::aabb::{AABB, surrounding_box};
 use crate::hittable::{HitRecord, Hittable};
 use crate::ray::Ray;
-use std::rc::Rc;
+use crate::sampler::Sampler;
+use crate::vec3::{Point3, Vec3};
+use std::sync::Arc;
 
 #[derive(Default)]
 pub struct HittableList {
-	pub objects: Vec<Rc<dyn Hittable>>,
+	pub objects: Vec<Arc<dyn Hittable>>,
 }
 
 impl HittableList {
-	pub fn new(object: Rc<dyn Hittable>) -> Self {
+	pub fn new(object: Arc<dyn Hittable>) -> Self {
 		HittableList {
 			objects: vec![object],
 		}
@@ -19,7 +21,7 @@ impl HittableList {
 		self.objects.clear();
 	}
 
-	pub fn add(&mut self, object: Rc<dyn Hittable>) {
+	pub fn add(&mut self, object: Arc<dyn Hittable>) {
 		self.objects.push(object);
 	}
 }
@@ -48,13 +50,25 @@ impl Hittable for HittableList {
 		let mut first_box = true;
 
 		for object in &self.objects {
-			if let Some(temp_box) = object.bounding_box(time0, time1){
-				output_box = if first_box {temp_box} else { surrounding_box(output_box, temp_box) };
-				first_box = false;
-			}
-			return None;
+			let temp_box = object.bounding_box(time0, time1)?;
+			output_box = if first_box { temp_box } else { surrounding_box(output_box, temp_box) };
+			first_box = false;
 		}
 
 		Some(output_box)
 	}
+	/// Every object is picked with the same probability, so the density is their average.
+	fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+		if self.objects.is_empty() {
+			return 0.0;
+		}
+
+		let sum: f64 = self.objects.iter().map(|object| object.pdf_value(origin, direction)).sum();
+		sum / self.objects.len() as f64
+	}
+
+	fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vec3 {
+		let index = (sampler.get_1d() * self.objects.len() as f64) as usize;
+		self.objects[index.min(self.objects.len() - 1)].random(origin, sampler)
+	}
 }
@@ -1,456 +1,253 @@
-#![feature(destructuring_assignment)]
+use std::{path::Path, process::exit, time::Instant};
 
-use std::{fs::File, io::Write, rc::Rc, time::Instant};
+use clap::{CommandFactory, ErrorKind, FromArgMatches, Parser};
 
 use crate::{
-	_box::Box,
-	aarect::{XYRect, XZRect, YZRect},
-	bvh::BVHNode,
+	bvh::accelerate,
 	camera::Camera,
-	color::write_color,
-	constantmedium::ConstantMedium,
-	hittable::{Hittable, RotateY, Translate},
-	hittable_list::HittableList,
-	material::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
-	movingsphere::MovingSphere,
-	ray::Ray,
-	sphere::Sphere,
-	texture::{CheckerTexture, ImageTexture, NoiseTexture},
-	util::{random_f64, random_f64_range},
-	vec3::{Color, Point3, Vec3},
+	framebuffer::OutputFormat,
+	render::{BounceLimits, compare_bvhs, Heuristic, render, Tracer},
+	sampler::SamplerKind,
+	scenes::{scene, SCENES},
+	vec3::{Point3, Vec3},
 };
 
 mod _box;
 mod aabb;
 mod aarect;
+mod animatedtransform;
 mod bvh;
 mod camera;
 mod color;
+mod cone;
 mod constantmedium;
+mod csg;
+mod cylinder;
+mod disk;
+mod framebuffer;
+mod gltf_import;
 mod hittable;
 mod hittable_list;
 mod material;
+mod matrix;
+mod mesh;
 mod movingsphere;
+mod obj;
+mod onb;
+mod pdf;
 mod perlin;
+mod plane;
+mod ply;
+mod quad;
+mod quat;
 mod ray;
+mod render;
+mod sampler;
+mod scene_file;
+mod scenes;
+mod sdf;
 mod sphere;
+mod stl;
 mod texture;
+mod torus;
+mod triangle;
 mod util;
 mod vec3;
 
-fn ray_color(r: &Ray, background: &Color, world: &dyn Hittable, depth: i32,) -> Color {
-	if depth <= 0 {
-		return Color::default();
-	}
+/// Renders a built-in scene or a JSON scene file to an image.
+#[derive(Parser,)]
+#[clap(arg_required_else_help = true)]
+struct Options {
+	/// Built-in scene name or path to a JSON scene file
+	scene: String,
 
-	if let Some(rec,) = world.hit(r, 0.001, f64::INFINITY,) {
-		let emitted = rec.mat_ptr.emitted(rec.u, rec.v, &rec.p,);
-		if let Some((attenuation, scattered,),) = rec.mat_ptr.scatter(r, &rec,) {
-			return emitted + attenuation * ray_color(&scattered, background, world, depth - 1,);
-		}
-		return emitted;
-	}
+	/// Output file, its extension picks the format: png, jpg, ppm, bmp, hdr or pfm
+	#[clap(short, long, default_value = "test.ppm")]
+	output: String,
 
-	*background
-}
+	/// Image width in pixels [default: per scene]
+	#[clap(short, long)]
+	width: Option<usize,>,
 
-fn random_scene() -> HittableList {
-	let mut world = HittableList::default();
-
-	let checker = Rc::new(CheckerTexture::new(
-		Color::new(0.2, 0.3, 0.1,),
-		Color::new(0.9, 0.9, 0.9,),
-	),);
-	let ground_material = Rc::new(Lambertian::from(checker,),);
-	world.add(Rc::new(Sphere::new(
-		Point3::new(0.0, -1000.0, 0.0,),
-		1000.0,
-		ground_material,
-	),),);
-
-	for a in -11 .. 11 {
-		for b in -11 .. 11 {
-			let choose_mat = random_f64();
-			let center = Point3::new(
-				a as f64 + 0.9 * random_f64(),
-				0.2,
-				b as f64 + 0.9 * random_f64(),
-			);
+	/// Image width divided by height [default: per scene]
+	#[clap(short, long)]
+	aspect_ratio: Option<f64,>,
 
-			if (center - Point3::new(4.0, 0.2, 0.0,)).length() > 0.9 {
-				let sphere_material: Rc<dyn Material,>;
-
-				if choose_mat < 0.8 {
-					let albedo = Color::random() * Color::random();
-					sphere_material = Rc::new(Lambertian::new(albedo,),);
-					let center2 = center + Vec3::new(0.0, random_f64_range(0.0, 0.5,), 0.0,);
-					world.add(Rc::new(MovingSphere::new(
-						center,
-						center2,
-						0.0,
-						1.0,
-						0.2,
-						sphere_material,
-					),),);
-				} else if choose_mat < 0.95 {
-					let albedo = Color::random_in_range(0.5, 1.0,);
-					let fuzz = random_f64_range(0.0, 0.5,);
-					sphere_material = Rc::new(Metal::new(albedo, fuzz,),);
-					world.add(Rc::new(Sphere::new(center, 0.2, sphere_material,),),);
-				} else {
-					sphere_material = Rc::new(Dielectric::new(1.5,),);
-					world.add(Rc::new(Sphere::new(center, 0.2, sphere_material,),),);
-				}
-			}
-		}
-	}
+	/// Samples per pixel [default: per scene]
+	#[clap(short, long)]
+	samples: Option<i32,>,
 
-	let material1 = Rc::new(Dielectric::new(1.5,),);
-	world.add(Rc::new(Sphere::new(
-		Point3::new(0.0, 1.0, 0.0,),
-		1.0,
-		material1,
-	),),);
+	/// Maximum number of bounces per path
+	#[clap(short = 'd', long, default_value_t = 50)]
+	max_depth: i32,
 
-	let material2 = Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1,),),);
-	world.add(Rc::new(Sphere::new(
-		Point3::new(-4.0, 1.0, 0.0,),
-		1.0,
-		material2,
-	),),);
+	/// Maximum number of diffuse bounces per path [default: max depth]
+	#[clap(long)]
+	max_diffuse: Option<i32,>,
 
-	let material3 = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5,), 0.0,),);
-	world.add(Rc::new(Sphere::new(
-		Point3::new(4.0, 1.0, 0.0,),
-		1.0,
-		material3,
-	),),);
+	/// Maximum number of glossy and mirror bounces per path [default: max depth]
+	#[clap(long)]
+	max_glossy: Option<i32,>,
 
-	world
-}
+	/// Maximum number of refractions per path [default: max depth]
+	#[clap(long)]
+	max_transmission: Option<i32,>,
 
-fn two_spheres() -> HittableList {
-	let mut objects = HittableList::default();
-
-	let checker = Rc::new(CheckerTexture::new(
-		Color::new(0.2, 0.3, 0.1,),
-		Color::new(0.9, 0.9, 0.9,),
-	),);
-	objects.add(Rc::new(Sphere::new(
-		Point3::new(0.0, -10.0, 0.0,),
-		10.0,
-		Rc::new(Lambertian::from(checker.clone(),),),
-	),),);
-	objects.add(Rc::new(Sphere::new(
-		Point3::new(0.0, 10.0, 0.0,),
-		10.0,
-		Rc::new(Lambertian::from(checker,),),
-	),),);
-
-	objects
-}
+	/// Maximum number of scatterings in volumes per path [default: max depth]
+	#[clap(long)]
+	max_volume: Option<i32,>,
 
-fn two_perlin_spheres() -> HittableList {
-	let mut objects = HittableList::default();
-
-	let pertext = Rc::new(NoiseTexture::new(4.0,),);
-	objects.add(Rc::new(Sphere::new(
-		Point3::new(0.0, -1000.0, 0.0,),
-		1000.0,
-		Rc::new(Lambertian::from(pertext.clone(),),),
-	),),);
-	objects.add(Rc::new(Sphere::new(
-		Point3::new(0.0, 2.0, 0.0,),
-		2.0,
-		Rc::new(Lambertian::from(pertext,),),
-	),),);
-
-	objects
-}
+	/// Bounces after which paths that carry little light are randomly terminated
+	#[clap(long, default_value_t = 3)]
+	roulette_depth: i32,
 
-fn earth() -> HittableList {
-	let earth_texture = Rc::new(ImageTexture::new("earthmap.jpg",),);
-	let earth_surface = Rc::new(Lambertian::from(earth_texture,),);
-	let globe = Rc::new(Sphere::new(Point3::default(), 2.0, earth_surface,),);
+	/// How light and material samples are combined: power or balance
+	#[clap(long, default_value = "power")]
+	heuristic: Heuristic,
 
-	HittableList::new(globe,)
-}
+	/// Where sample values come from: independent, stratified, halton or sobol
+	#[clap(long, default_value = "independent")]
+	sampler: SamplerKind,
 
-fn simple_light() -> HittableList {
-	let mut objects = HittableList::default();
-
-	let pertext = Rc::new(NoiseTexture::new(4.0,),);
-	objects.add(Rc::new(Sphere::new(
-		Point3::new(0.0, -1000.0, 0.0,),
-		1000.0,
-		Rc::new(Lambertian::from(pertext.clone(),),),
-	),),);
-	objects.add(Rc::new(Sphere::new(
-		Point3::new(0.0, 2.0, 0.0,),
-		2.0,
-		Rc::new(Lambertian::from(pertext,),),
-	),),);
-
-	let difflight = Rc::new(DiffuseLight::from(Color::new(4.0, 4.0, 4.0,),),);
-	objects.add(Rc::new(XYRect::new(
-		3.0,
-		5.0,
-		1.0,
-		3.0,
-		-2.0,
-		difflight.clone(),
-	),),);
-	objects.add(Rc::new(Sphere::new(
-		Point3::new(0.0, 7.0, 0.0,),
-		2.0,
-		difflight,
-	),),);
-
-	objects
-}
+	/// Camera position, as x,y,z
+	#[clap(long, allow_hyphen_values = true)]
+	lookfrom: Option<Point3,>,
 
-fn cornell_box() -> HittableList {
-	let mut objects = HittableList::default();
+	/// Point the camera looks at, as x,y,z
+	#[clap(long, allow_hyphen_values = true)]
+	lookat: Option<Point3,>,
 
-	let red = Rc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05,),),);
-	let white = Rc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73,),),);
-	let green = Rc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15,),),);
-	let light = Rc::new(DiffuseLight::from(Color::new(15.0, 15.0, 15.0,),),);
+	/// Camera up direction, as x,y,z
+	#[clap(long, allow_hyphen_values = true)]
+	vup: Option<Vec3,>,
 
-	objects.add(Rc::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green,),),);
-	objects.add(Rc::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red,),),);
-	objects.add(Rc::new(XZRect::new(
-		213.0, 343.0, 227.0, 332.0, 554.0, light,
-	),),);
-	objects.add(Rc::new(XZRect::new(
-		0.0,
-		555.0,
-		0.0,
-		555.0,
-		0.0,
-		white.clone(),
-	),),);
-	objects.add(Rc::new(XZRect::new(
-		0.0,
-		555.0,
-		0.0,
-		555.0,
-		555.0,
-		white.clone(),
-	),),);
-	objects.add(Rc::new(XYRect::new(
-		0.0,
-		555.0,
-		0.0,
-		555.0,
-		555.0,
-		white.clone(),
-	),),);
-
-	let box1 = Rc::new(Box::new(
-		&Point3::new(0.0, 0.0, 0.0,),
-		&Point3::new(165.0, 330.0, 165.0,),
-		white.clone(),
-	),);
-	let box1 = Rc::new(RotateY::new(box1, 15.0,),);
-	let box1 = Rc::new(Translate::new(box1, Vec3::new(265.0, 0.0, 295.0,),),);
-	objects.add(box1,);
-
-	let box2 = Rc::new(Box::new(
-		&Point3::new(0.0, 0.0, 0.0,),
-		&Point3::new(165.0, 165.0, 165.0,),
-		white,
-	),);
-	let box2 = Rc::new(RotateY::new(box2, -18.0,),);
-	let box2 = Rc::new(Translate::new(box2, Vec3::new(130.0, 0.0, 65.0,),),);
-	objects.add(box2,);
-
-	objects
-}
+	/// Vertical field of view in degrees
+	#[clap(long)]
+	vfov: Option<f64,>,
 
-fn cornell_smoke() -> HittableList {
-	let mut objects = HittableList::default();
+	/// Lens aperture, 0 disables depth of field
+	#[clap(long)]
+	aperture: Option<f64,>,
 
-	let red = Rc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05,),),);
-	let white = Rc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73,),),);
-	let green = Rc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15,),),);
-	let light = Rc::new(DiffuseLight::from(Color::new(7.0, 7.0, 7.0,),),);
+	/// Distance to the plane in focus
+	#[clap(long)]
+	focus_dist: Option<f64,>,
 
-	objects.add(Rc::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green,),),);
-	objects.add(Rc::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red,),),);
-	objects.add(Rc::new(XZRect::new(
-		113.0, 443.0, 127.0, 432.0, 554.0, light,
-	),),);
-	objects.add(Rc::new(XZRect::new(
-		0.0,
-		555.0,
-		0.0,
-		555.0,
-		0.0,
-		white.clone(),
-	),),);
-	objects.add(Rc::new(XZRect::new(
-		0.0,
-		555.0,
-		0.0,
-		555.0,
-		555.0,
-		white.clone(),
-	),),);
-	objects.add(Rc::new(XYRect::new(
-		0.0,
-		555.0,
-		0.0,
-		555.0,
-		555.0,
-		white.clone(),
-	),),);
-
-	let box1 = Rc::new(Box::new(
-		&Point3::new(0.0, 0.0, 0.0,),
-		&Point3::new(165.0, 330.0, 165.0,),
-		white.clone(),
-	),);
-	let box1 = Rc::new(RotateY::new(box1, 15.0,),);
-	let box1 = Rc::new(Translate::new(box1, Vec3::new(265.0, 0.0, 295.0,),),);
-	objects.add(Rc::new(ConstantMedium::from_color(
-		box1,
-		0.01,
-		Color::default(),
-	),),);
-
-	let box2 = Rc::new(Box::new(
-		&Point3::new(0.0, 0.0, 0.0,),
-		&Point3::new(165.0, 165.0, 165.0,),
-		white,
-	),);
-	let box2 = Rc::new(RotateY::new(box2, -18.0,),);
-	let box2 = Rc::new(Translate::new(box2, Vec3::new(130.0, 0.0, 65.0,),),);
-	objects.add(Rc::new(ConstantMedium::from_color(
-		box2,
-		0.01,
-		Color::new(1.0, 1.0, 1.0,),
-	),),);
-
-	objects
+	/// After rendering, build a pointer BVH next to the flattened one and compare their speed
+	#[clap(long)]
+	bvh_stats: bool,
 }
 
 fn main() {
-	let mut aspect_ratio = 16.0 / 9.0;
-	let mut image_width = 400;
-	let mut samples_per_pixel = 100;
-	let max_depth = 50;
-
-	let world;
-	let lookfrom;
-	let lookat;
-	let mut vfov = 40.0;
-	let mut aperture = 0.0;
-	let mut background = Color::default();
-
-	match 0 {
-		1 => {
-			world = random_scene();
-			background = Color::new(0.70, 0.80, 1.00,);
-			lookfrom = Point3::new(13.0, 2.0, 3.0,);
-			lookat = Point3::new(0.0, 0.0, 0.0,);
-			vfov = 20.0;
-			aperture = 0.1;
-		},
-		2 => {
-			world = two_spheres();
-			background = Color::new(0.70, 0.80, 1.00,);
-			lookfrom = Point3::new(13.0, 2.0, 3.0,);
-			lookat = Point3::new(0.0, 0.0, 0.0,);
-			vfov = 20.0;
-		},
-		3 => {
-			world = two_perlin_spheres();
-			background = Color::new(0.70, 0.80, 1.00,);
-			lookfrom = Point3::new(13.0, 2.0, 3.0,);
-			lookat = Point3::new(0.0, 0.0, 0.0,);
-			vfov = 20.0;
-		},
-		4 => {
-			world = earth();
-			background = Color::new(0.70, 0.80, 1.00,);
-			lookfrom = Point3::new(13.0, 2.0, 3.0,);
-			lookat = Point3::default();
-			vfov = 20.0;
-		},
-		5 => {
-			world = simple_light();
-			samples_per_pixel = 400;
-			background = Color::default();
-			lookfrom = Point3::new(26.0, 3.0, 6.0,);
-			lookat = Point3::new(0.0, 2.0, 0.0,);
-			vfov = 20.0;
-		},
-		6 => {
-			world = cornell_box();
-			aspect_ratio = 1.0;
-			image_width = 600;
-			samples_per_pixel = 200;
-			background = Color::default();
-			lookfrom = Point3::new(278.0, 278.0, -800.0,);
-			lookat = Point3::new(278.0, 278.0, 0.0,);
-			vfov = 40.0;
-		},
-		_ => {
-			world = cornell_smoke();
-			aspect_ratio = 1.0;
-			image_width = 600;
-			samples_per_pixel = 200;
-			background = Color::default();
-			lookfrom = Point3::new(278.0, 278.0, -800.0,);
-			lookat = Point3::new(278.0, 278.0, 0.0,);
-			vfov = 40.0;
-		}
+	let scenes = format!("SCENES:\n    {}", SCENES.join("\n    "));
+	let mut command = Options::command().after_help(scenes.as_str(),);
+	let options =
+		Options::from_arg_matches(&command.get_matches_mut(),).unwrap_or_else(|e| e.exit(),);
+
+	let output = Path::new(&options.output,);
+	let format = OutputFormat::from_path(output,).unwrap_or_else(|e| {
+		command
+			.error(
+				ErrorKind::InvalidValue,
+				format!("cannot save `{}`: {}", options.output, e),
+			)
+			.exit()
+	},);
+
+	let scene = match scene(&options.scene,) {
+		Some(scene,) => scene,
+		None => scene_file::load(Path::new(&options.scene,),).unwrap_or_else(|e| {
+			command
+				.error(
+					ErrorKind::InvalidValue,
+					format!("cannot load `{}`: {}", options.scene, e),
+				)
+				.exit()
+		},),
+	};
+
+	let aspect_ratio = options.aspect_ratio.unwrap_or(scene.aspect_ratio,);
+	let image_width = options.width.unwrap_or(scene.image_width,);
+	let image_height = (image_width as f64 / aspect_ratio) as usize;
+	let samples_per_pixel = options.samples.unwrap_or(scene.samples_per_pixel,);
+	let max_depth = options.max_depth;
+
+	let invalid = if image_width == 0 {
+		Some("the image width must be at least 1".to_string(),)
+	} else if !(aspect_ratio > 0.0 && aspect_ratio.is_finite()) {
+		Some(format!(
+			"the aspect ratio must be a positive number, not {}",
+			aspect_ratio
+		),)
+	} else if image_height == 0 {
+		Some(format!(
+			"an aspect ratio of {} leaves no rows at a width of {}",
+			aspect_ratio, image_width
+		),)
+	} else if samples_per_pixel < 1 {
+		Some(format!(
+			"the samples per pixel must be at least 1, not {}",
+			samples_per_pixel
+		),)
+	} else {
+		None
+	};
+	if let Some(message,) = invalid {
+		command.error(ErrorKind::InvalidValue, message,).exit();
 	}
 
-	let image_height = (image_width as f64 / aspect_ratio) as i32;
-	// let world = BVHNode::from_list(&world, 0.0, 1.0,);
-
-	let vup = Vec3::new(0.0, 1.0, 0.0,);
-	let dist_to_focus = 10.0;
+	let world = accelerate(&scene.world, 0.0, 1.0,);
+	let background = scene.background;
 
 	let cam = Camera::new(
-		lookfrom,
-		lookat,
-		vup,
-		vfov,
+		options.lookfrom.unwrap_or(scene.lookfrom,),
+		options.lookat.unwrap_or(scene.lookat,),
+		options.vup.unwrap_or(scene.vup,),
+		options.vfov.unwrap_or(scene.vfov,),
 		aspect_ratio,
-		aperture,
-		dist_to_focus,
+		options.aperture.unwrap_or(scene.aperture,),
+		options.focus_dist.unwrap_or(scene.focus_dist,),
 		0.0,
 		1.0,
 	);
 
-	let mut file = File::create("test.ppm",).unwrap();
-
-	write!(file, "P3\n{} {}\n255\n", image_width, image_height).unwrap();
-
-	let mut time_per_line = 0.0;
-	let mut max = 0.0;
-
-	for j in (0 .. image_height).into_iter().rev() {
-		let now = Instant::now();
-
-		for i in 0 .. image_width {
-			let mut pixel_color = Color::default();
-			for _ in 0 .. samples_per_pixel {
-				let u = (i as f64 + random_f64()) / (image_width - 1) as f64;
-				let v = (j as f64 + random_f64()) / (image_height - 1) as f64;
-				let r = cam.get_ray(u, v,);
-				pixel_color += ray_color(&r, &background, &world, max_depth,);
-			}
-			write_color(&mut file, &pixel_color, samples_per_pixel,);
+	let now = Instant::now();
+	let tracer = Tracer {
+		world: &world,
+		lights: &scene.lights,
+		background,
+		max_depth,
+		limits: BounceLimits {
+			diffuse:      options.max_diffuse.unwrap_or(max_depth,),
+			glossy:       options.max_glossy.unwrap_or(max_depth,),
+			transmission: options.max_transmission.unwrap_or(max_depth,),
+			volume:       options.max_volume.unwrap_or(max_depth,),
+		},
+		roulette_depth: options.roulette_depth,
+		heuristic: options.heuristic,
+	};
+	let image = render(
+		&tracer,
+		&cam,
+		image_width,
+		image_height,
+		samples_per_pixel,
+		options.sampler,
+	);
+	eprint!("\nDone\n{:.1}s;\n", now.elapsed().as_secs_f64());
+
+	if options.bvh_stats {
+		if let Some(comparison,) = compare_bvhs(&scene.world, &cam,) {
+			eprintln!(
+				"BVH: {} nodes; flattened traversal {:.1}x as fast as the pointer tree;",
+				comparison.nodes,
+				comparison.speedup()
+			);
 		}
-
-		time_per_line = 0.9 * time_per_line + 0.1 * now.elapsed().as_secs_f64();
-		max = (time_per_line * j as f64).max(max,);
-		eprint!("\r{} scanlines; {:.1}s;", j, time_per_line * j as f64);
 	}
 
-	eprint!("\nDone\n{:.1}s;\n", max);
+	if let Err(e,) = image.save(output, format,) {
+		eprintln!("cannot save `{}`: {}", options.output, e);
+		exit(1,);
+	}
 }
@@ -1,80 +1,166 @@
-use std::rc::Rc;
+use std::{f64::consts::PI, sync::Arc};
 
 use crate::{
 	hittable::HitRecord,
+	pdf::{CosinePdf, Pdf, PhongPdf, SpherePdf},
 	ray::Ray,
+	sampler::Sampler,
 	texture::{SolidColor, Texture},
-	util::random_f64,
-	vec3::{
-		Color, dot, Point3, random_in_unit_sphere, random_unit_vector, reflect, refract,
-		unit_vector, Vec3,
-	},
+	vec3::{Color, dot, Point3, reflect, refract, unit_vector, Vec3},
 };
 
-pub trait Material {
-	fn scatter(&self, r_in: &Ray, rec: &HitRecord,) -> Option<(Color, Ray,),>;
+/// What kind of bounce a scattered path takes, each kind has its own limit on how often a path
+/// may take it.
+#[derive(Clone, Copy,)]
+pub enum Bounce {
+	Diffuse,
+	Glossy,
+	Transmission,
+	Volume,
+}
+
+/// How a material continues a path.
+pub enum ScatterRecord {
+	/// A single direction, like a mirror reflection, that no density describes. The path just
+	/// follows `ray` and is weighed by `attenuation`.
+	Specular {
+		attenuation: Color,
+		ray:         Ray,
+		bounce:      Bounce,
+	},
+	/// Directions drawn from a density, weighed by `Material::eval` divided by the density.
+	Pdf {
+		pdf:    Box<dyn Pdf,>,
+		bounce: Bounce,
+	},
+}
+
+pub trait Material: Send + Sync {
+	fn scatter(
+		&self,
+		r_in: &Ray,
+		rec: &HitRecord,
+		sampler: &mut dyn Sampler,
+	) -> Option<ScatterRecord,>;
+
+	/// The reflected fraction of light arriving along `scattered`, times the cosine with the
+	/// normal. Only materials that scatter by a density need this.
+	fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray,) -> Color { Color::default() }
+
+	/// The density with which `scatter` picks `scattered`.
+	fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray,) -> f64 { 0.0 }
 
 	fn emitted(&self, uu: f64, v: f64, p: &Point3,) -> Color { Color::new(0.0, 0.0, 0.0,) }
 }
 
 pub struct Lambertian {
-	pub albedo: Rc<dyn Texture,>,
+	pub albedo: Arc<dyn Texture,>,
 }
 
 impl Lambertian {
 	pub fn new(albedo: Color,) -> Self {
 		Lambertian {
-			albedo: Rc::new(SolidColor::from(albedo,),),
+			albedo: Arc::new(SolidColor::from(albedo,),),
 		}
 	}
 }
 
-impl<T: 'static,> From<Rc<T,>,> for Lambertian
+impl<T: 'static,> From<Arc<T,>,> for Lambertian
 where T: Texture
 {
-	fn from(texture: Rc<T,>,) -> Self { Lambertian { albedo: texture, } }
+	fn from(texture: Arc<T,>,) -> Self { Lambertian { albedo: texture, } }
 }
 
 impl Material for Lambertian {
-	fn scatter(&self, r_in: &Ray, rec: &HitRecord,) -> Option<(Color, Ray,),> {
-		// let scatter_direction = rec.p + rec.normal + random_in_unit_sphere();
-		let mut scatter_direction = rec.normal + random_unit_vector();
-		// let scatter_direction = rec.p + random_in_hemisphere(&rec.normal);
+	fn scatter(
+		&self,
+		_r_in: &Ray,
+		rec: &HitRecord,
+		_sampler: &mut dyn Sampler,
+	) -> Option<ScatterRecord,> {
+		Some(ScatterRecord::Pdf {
+			pdf:    Box::new(CosinePdf::new(&rec.normal,),),
+			bounce: Bounce::Diffuse,
+		},)
+	}
 
-		if scatter_direction.near_zero() {
-			scatter_direction = rec.normal;
-		}
+	fn eval(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray,) -> Color {
+		let cosine = dot(&rec.normal, &unit_vector(&scattered.direction(),),);
+		self.albedo.value(rec.u, rec.v, &rec.p,) * (cosine.max(0.0,) / PI)
+	}
 
-		Some((
-			self.albedo.value(rec.u, rec.v, &rec.p,),
-			Ray::new(rec.p, scatter_direction, r_in.time(),),
-		),)
+	fn pdf(&self, _r_in: &Ray, rec: &HitRecord, scattered: &Ray,) -> f64 {
+		let cosine = dot(&rec.normal, &unit_vector(&scattered.direction(),),);
+		cosine.max(0.0,) / PI
 	}
 }
 
+/// A mirror for a `fuzz` of zero, otherwise a glossy reflector whose lobe around the mirror
+/// direction widens with `fuzz`. The lobe is a modified Phong lobe, `fuzz` of one makes it a
+/// cosine lobe and smaller values narrow it about as `1 / fuzz²`. The exponent borrows the
+/// Blinn-Phong stand-in for a Beckmann roughness, which describes a lobe of half vectors rather
+/// than one around the mirror direction, so it is only a rule of thumb here.
 pub struct Metal {
-	pub albedo: Color,
+	pub albedo: Arc<dyn Texture,>,
 	pub fuzz:   f64,
 }
 
 impl Metal {
-	pub fn new(albedo: Vec3, fuzz: f64,) -> Self { Metal { albedo, fuzz, } }
+	pub fn new(albedo: Vec3, fuzz: f64,) -> Self {
+		Metal {
+			albedo: Arc::new(SolidColor::from(albedo,),),
+			fuzz,
+		}
+	}
+
+	fn exponent(&self,) -> f64 { (2.0 / (self.fuzz * self.fuzz) - 2.0).max(0.0,) }
+
+	fn lobe(&self, r_in: &Ray, rec: &HitRecord,) -> PhongPdf {
+		let reflected = reflect(&unit_vector(&r_in.direction(),), &rec.normal,);
+		PhongPdf::new(&reflected, self.exponent(),)
+	}
 }
 
 impl Material for Metal {
-	fn scatter(&self, r_in: &Ray, rec: &HitRecord,) -> Option<(Vec3, Ray,),> {
+	fn scatter(
+		&self,
+		r_in: &Ray,
+		rec: &HitRecord,
+		_sampler: &mut dyn Sampler,
+	) -> Option<ScatterRecord,> {
+		if self.fuzz > 0.0 {
+			return Some(ScatterRecord::Pdf {
+				pdf:    Box::new(self.lobe(r_in, rec,),),
+				bounce: Bounce::Glossy,
+			},);
+		}
+
 		let reflected = reflect(&unit_vector(&r_in.direction(),), &rec.normal,);
-		let scattered = Ray::new(
-			rec.p,
-			reflected + self.fuzz * random_unit_vector(),
-			r_in.time(),
-		);
-		let attenuation = self.albedo;
-		if dot(&scattered.direction(), &rec.normal,) <= 0.0 {
-			None
-		} else {
-			Some((attenuation, scattered,),)
+		Some(ScatterRecord::Specular {
+			attenuation: self.albedo.value(rec.u, rec.v, &rec.p,),
+			ray:         Ray::new(rec.p, reflected, r_in.time(),),
+			bounce:      Bounce::Glossy,
+		},)
+	}
+
+	/// Directions in the lobe that point into the surface are absorbed.
+	fn eval(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray,) -> Color {
+		let cosine = dot(&rec.normal, &unit_vector(&scattered.direction(),),);
+		if cosine <= 0.0 {
+			return Color::default();
 		}
+
+		let exponent = self.exponent();
+		let lobe = self.lobe(r_in, rec,).value(&scattered.direction(),);
+		let albedo = self.albedo.value(rec.u, rec.v, &rec.p,);
+		// `lobe` is `(exponent + 1) / 2π cos^exponent`, rescaled to the `(exponent + 2) / 2π` of
+		// the modified Phong BRDF. Light arriving along the normal is then reflected in full, at
+		// other angles part of the lobe falls below the surface and is lost.
+		albedo * (lobe * (exponent + 2.0) / (exponent + 1.0) * cosine)
+	}
+
+	fn pdf(&self, r_in: &Ray, rec: &HitRecord, scattered: &Ray,) -> f64 {
+		self.lobe(r_in, rec,).value(&scattered.direction(),)
 	}
 }
 
@@ -93,7 +179,12 @@ impl Dielectric {
 }
 
 impl Material for Dielectric {
-	fn scatter(&self, r_in: &Ray, rec: &HitRecord,) -> Option<(Vec3, Ray,),> {
+	fn scatter(
+		&self,
+		r_in: &Ray,
+		rec: &HitRecord,
+		sampler: &mut dyn Sampler,
+	) -> Option<ScatterRecord,> {
 		let refraction_ration = if rec.front_face {
 			1.0 / self.ir
 		} else {
@@ -106,63 +197,124 @@ impl Material for Dielectric {
 
 		let cannot_refract = refraction_ration * sin_theta > 1.0;
 		let direction;
+		let bounce;
 
-		if cannot_refract || Dielectric::reflectance(cos_theta, refraction_ration,) > random_f64() {
+		if cannot_refract
+			|| Dielectric::reflectance(cos_theta, refraction_ration,) > sampler.get_1d()
+		{
 			direction = reflect(&unit_direction, &rec.normal,);
+			bounce = Bounce::Glossy;
 		} else {
-			direction = refract(&unit_direction, &rec.normal, refraction_ration,)
+			direction = refract(&unit_direction, &rec.normal, refraction_ration,);
+			bounce = Bounce::Transmission;
 		}
 
-		Some((
-			Color::new(1.0, 1.0, 1.0,),
-			Ray::new(rec.p, direction, r_in.time(),),
-		),)
+		Some(ScatterRecord::Specular {
+			attenuation: Color::new(1.0, 1.0, 1.0,),
+			ray: Ray::new(rec.p, direction, r_in.time(),),
+			bounce,
+		},)
 	}
 }
 
 pub struct DiffuseLight {
-	emit: Rc<dyn Texture,>,
+	emit: Arc<dyn Texture,>,
 }
 
 impl DiffuseLight {
-	pub fn new(emit: Rc<dyn Texture,>,) -> Self { DiffuseLight { emit, } }
+	pub fn new(emit: Arc<dyn Texture,>,) -> Self { DiffuseLight { emit, } }
 }
 
 impl From<Color,> for DiffuseLight {
 	fn from(color: Color,) -> Self {
 		DiffuseLight {
-			emit: Rc::new(SolidColor::from(color,),),
+			emit: Arc::new(SolidColor::from(color,),),
 		}
 	}
 }
 
 impl Material for DiffuseLight {
-	fn scatter(&self, r_in: &Ray, rec: &HitRecord,) -> Option<(Vec3, Ray,),> { None }
+	fn scatter(
+		&self,
+		_r_in: &Ray,
+		_rec: &HitRecord,
+		_sampler: &mut dyn Sampler,
+	) -> Option<ScatterRecord,> {
+		None
+	}
 
 	fn emitted(&self, u: f64, v: f64, p: &Vec3,) -> Vec3 { self.emit.value(u, v, p,) }
 }
 
 pub struct Isotrophic {
-	albedo: Rc<dyn Texture,>,
+	albedo: Arc<dyn Texture,>,
 }
 
 impl Isotrophic {
-	pub fn new(albedo: Rc<dyn Texture,>,) -> Self { Isotrophic { albedo, } }
+	pub fn new(albedo: Arc<dyn Texture,>,) -> Self { Isotrophic { albedo, } }
 }
 
 impl From<Color,> for Isotrophic {
 	fn from(c: Color,) -> Self {
 		Isotrophic {
-			albedo: Rc::new(SolidColor::from(c,),),
+			albedo: Arc::new(SolidColor::from(c,),),
 		}
 	}
 }
 
 impl Material for Isotrophic {
-	fn scatter(&self, r_in: &Ray, rec: &HitRecord,) -> Option<(Vec3, Ray,),> {
-		Some((
-			self.albedo.value(rec.u, rec.v, &rec.p,),
-			Ray::new(rec.p, random_in_unit_sphere(), r_in.time(),),
-		),)
+	fn scatter(
+		&self,
+		_r_in: &Ray,
+		_rec: &HitRecord,
+		_sampler: &mut dyn Sampler,
+	) -> Option<ScatterRecord,> {
+		Some(ScatterRecord::Pdf {
+			pdf:    Box::new(SpherePdf,),
+			bounce: Bounce::Volume,
+		},)
+	}
+
+	fn eval(&self, _r_in: &Ray, rec: &HitRecord, _scattered: &Ray,) -> Color {
+		self.albedo.value(rec.u, rec.v, &rec.p,) / (4.0 * PI)
+	}
+
+	fn pdf(&self, _r_in: &Ray, _rec: &HitRecord, _scattered: &Ray,) -> f64 { 1.0 / (4.0 * PI) }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{aarect::XZRect, hittable::Hittable, sampler::IndependentSampler};
+
+	/// The fraction of light arriving along `incoming` at a white `fuzz` metal that it reflects.
+	fn reflectance(fuzz: f64, incoming: Vec3,) -> f64 {
+		let metal = Arc::new(Metal::new(Color::new(1.0, 1.0, 1.0,), fuzz,),);
+		let floor = XZRect::new(-1.0, 1.0, -1.0, 1.0, 0.0, metal.clone(),);
+		let r_in = Ray::new(-incoming, incoming, 0.0,);
+		let rec = floor.hit(&r_in, 0.001, f64::INFINITY,).unwrap();
+
+		let mut sampler = IndependentSampler;
+		let samples = 100_000;
+		let mut total = 0.0;
+		for _ in 0 .. samples {
+			let scattered = Ray::new(rec.p, metal.lobe(&r_in, &rec,).generate(&mut sampler,), 0.0,);
+			let pdf = metal.pdf(&r_in, &rec, &scattered,);
+			if pdf > 0.0 {
+				total += metal.eval(&r_in, &rec, &scattered,).x() / pdf;
+			}
+		}
+		total / samples as f64
+	}
+
+	#[test]
+	fn fuzzy_metal_reflects_no_more_than_it_receives() {
+		for &fuzz in &[0.1, 0.5, 1.0,] {
+			let normal = reflectance(fuzz, Vec3::new(0.0, -1.0, 0.0,),);
+			assert!((normal - 1.0).abs() < 0.01, "fuzz {} reflects {}", fuzz, normal);
+
+			let grazing = reflectance(fuzz, unit_vector(&Vec3::new(1.0, -0.2, 0.0,),),);
+			assert!(grazing < normal, "fuzz {} reflects {} at grazing", fuzz, grazing);
+		}
 	}
 }
@@ -0,0 +1,379 @@
+use std::ops::Mul;
+
+use crate::{
+	quat::Quat,
+	util::degrees_to_radians,
+	vec3::{cross, dot, unit_vector, Point3, Vec3},
+};
+
+/// A 3x3 matrix stored row by row, `m[row][column]`, acting on column vectors.
+#[derive(Clone, Copy,)]
+pub struct Mat3 {
+	pub m: [[f64; 3]; 3],
+}
+
+impl Default for Mat3 {
+	fn default() -> Self { Mat3::identity() }
+}
+
+impl Mat3 {
+	pub fn new(m: [[f64; 3]; 3],) -> Self { Mat3 { m, } }
+
+	/// The matrix with `x`, `y` and `z` as its columns.
+	pub fn from_columns(x: Vec3, y: Vec3, z: Vec3,) -> Self {
+		Mat3::new([[x[0], y[0], z[0],], [x[1], y[1], z[1],], [x[2], y[2], z[2],],],)
+	}
+
+	pub fn identity() -> Self { Mat3::scaling(Vec3::new(1.0, 1.0, 1.0,),) }
+
+	pub fn scaling(factors: Vec3,) -> Self {
+		Mat3::new([
+			[factors[0], 0.0, 0.0,],
+			[0.0, factors[1], 0.0,],
+			[0.0, 0.0, factors[2],],
+		],)
+	}
+
+	/// A counter-clockwise rotation by `angle` degrees about `axis`, seen looking down the axis.
+	pub fn rotation(axis: Vec3, angle: f64,) -> Self {
+		let a = unit_vector(&axis,);
+		let (sin, cos,) = degrees_to_radians(angle,).sin_cos();
+		let t = 1.0 - cos;
+
+		Mat3::new([
+			[
+				t * a.x() * a.x() + cos,
+				t * a.x() * a.y() - sin * a.z(),
+				t * a.x() * a.z() + sin * a.y(),
+			],
+			[
+				t * a.x() * a.y() + sin * a.z(),
+				t * a.y() * a.y() + cos,
+				t * a.y() * a.z() - sin * a.x(),
+			],
+			[
+				t * a.x() * a.z() - sin * a.y(),
+				t * a.y() * a.z() + sin * a.x(),
+				t * a.z() * a.z() + cos,
+			],
+		],)
+	}
+
+	pub fn column(&self, c: usize,) -> Vec3 { Vec3::new(self.m[0][c], self.m[1][c], self.m[2][c],) }
+
+	pub fn transpose(&self,) -> Self {
+		Mat3::from_columns(
+			Vec3 { e: self.m[0], },
+			Vec3 { e: self.m[1], },
+			Vec3 { e: self.m[2], },
+		)
+	}
+
+	pub fn determinant(&self,) -> f64 {
+		dot(&self.column(0,), &cross(&self.column(1,), &self.column(2,),),)
+	}
+
+	/// The adjugate divided by the determinant, `None` for a singular matrix or one with entries
+	/// that are not finite.
+	pub fn inverse(&self,) -> Option<Self,> {
+		let det = self.determinant();
+		if !det.is_finite() || det.abs() < 1e-12 {
+			return None;
+		}
+
+		// The rows of the inverse are the cross products of pairs of columns.
+		let (x, y, z,) = (self.column(0,), self.column(1,), self.column(2,),);
+		let rows = [cross(&y, &z,) / det, cross(&z, &x,) / det, cross(&x, &y,) / det,];
+		Some(Mat3::new(rows.map(|row| row.e,),),)
+	}
+
+	/// Transforms a surface normal so it stays perpendicular to transformed tangents, by the
+	/// inverse transpose. The result is not normalized.
+	pub fn transform_normal(&self, n: &Vec3,) -> Option<Vec3,> {
+		self.inverse().map(|inverse| inverse.transpose() * *n,)
+	}
+}
+
+impl Mul for Mat3 {
+	type Output = Mat3;
+
+	fn mul(self, rhs: Mat3,) -> Self::Output {
+		let mut matrix = Mat3::new([[0.0; 3]; 3],);
+		for r in 0 .. 3 {
+			for c in 0 .. 3 {
+				matrix.m[r][c] = (0 .. 3).map(|k| self.m[r][k] * rhs.m[k][c],).sum();
+			}
+		}
+		matrix
+	}
+}
+
+impl Mul<Vec3,> for Mat3 {
+	type Output = Vec3;
+
+	fn mul(self, v: Vec3,) -> Self::Output {
+		Vec3 {
+			e: self.m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2],),
+		}
+	}
+}
+
+impl From<Quat,> for Mat3 {
+	fn from(q: Quat,) -> Self {
+		Mat3::from_columns(
+			q.rotate(&Vec3::new(1.0, 0.0, 0.0,),),
+			q.rotate(&Vec3::new(0.0, 1.0, 0.0,),),
+			q.rotate(&Vec3::new(0.0, 0.0, 1.0,),),
+		)
+	}
+}
+
+/// A 4x4 matrix stored row by row, `m[row][column]`, acting on column vectors.
+#[derive(Clone, Copy,)]
+pub struct Mat4 {
+	pub m: [[f64; 4]; 4],
+}
+
+impl Default for Mat4 {
+	fn default() -> Self { Mat4::identity() }
+}
+
+impl Mat4 {
+	pub fn new(m: [[f64; 4]; 4],) -> Self { Mat4 { m, } }
+
+	pub fn identity() -> Self {
+		Mat4::new([
+			[1.0, 0.0, 0.0, 0.0,],
+			[0.0, 1.0, 0.0, 0.0,],
+			[0.0, 0.0, 1.0, 0.0,],
+			[0.0, 0.0, 0.0, 1.0,],
+		],)
+	}
+
+	pub fn translation(offset: Vec3,) -> Self {
+		let mut matrix = Mat4::identity();
+		for r in 0 .. 3 {
+			matrix.m[r][3] = offset[r];
+		}
+		matrix
+	}
+
+	pub fn scaling(factors: Vec3,) -> Self { Mat4::from(Mat3::scaling(factors,),) }
+
+	/// A counter-clockwise rotation by `angle` degrees about `axis`, seen looking down the axis.
+	pub fn rotation(axis: Vec3, angle: f64,) -> Self { Mat4::from(Mat3::rotation(axis, angle,),) }
+
+	/// Places an object at `from` with its -z axis towards `to` and its y axis as close to `vup`
+	/// as possible, the same frame `Camera` uses.
+	pub fn look_at(from: Point3, to: Point3, vup: Vec3,) -> Self {
+		let w = unit_vector(&(from - to),);
+		let u = unit_vector(&cross(&vup, &w,),);
+		let v = cross(&w, &u,);
+
+		Mat4::translation(from,) * Mat4::from(Mat3::from_columns(u, v, w,),)
+	}
+
+	/// The upper left 3x3 block, which holds everything but the translation of an affine matrix.
+	pub fn linear(&self,) -> Mat3 {
+		Mat3::new([0, 1, 2,].map(|r| [self.m[r][0], self.m[r][1], self.m[r][2],],),)
+	}
+
+	pub fn transpose(&self,) -> Self {
+		let mut matrix = Mat4::identity();
+		for r in 0 .. 4 {
+			for c in 0 .. 4 {
+				matrix.m[r][c] = self.m[c][r];
+			}
+		}
+		matrix
+	}
+
+	/// Gauss-Jordan elimination with partial pivoting, `None` for a singular matrix or one with
+	/// entries that are not finite, like those built from a zero rotation axis.
+	pub fn inverse(&self,) -> Option<Self,> {
+		if self.m.iter().flatten().any(|x| !x.is_finite(),) {
+			return None;
+		}
+
+		let mut a = self.m;
+		let mut inverse = Mat4::identity().m;
+
+		for c in 0 .. 4 {
+			let pivot = (c .. 4).max_by(|&i, &j| a[i][c].abs().total_cmp(&a[j][c].abs(),),)?;
+			if a[pivot][c].abs() < 1e-12 {
+				return None;
+			}
+			a.swap(c, pivot,);
+			inverse.swap(c, pivot,);
+
+			let scale = 1.0 / a[c][c];
+			for k in 0 .. 4 {
+				a[c][k] *= scale;
+				inverse[c][k] *= scale;
+			}
+
+			for r in (0 .. 4).filter(|&r| r != c,) {
+				let factor = a[r][c];
+				for k in 0 .. 4 {
+					a[r][k] -= factor * a[c][k];
+					inverse[r][k] -= factor * inverse[c][k];
+				}
+			}
+		}
+
+		Some(Mat4::new(inverse,),)
+	}
+
+	pub fn transform_point(&self, p: &Point3,) -> Point3 {
+		let m = &self.m;
+		let mut result = Point3::default();
+		for r in 0 .. 3 {
+			result[r] = m[r][0] * p[0] + m[r][1] * p[1] + m[r][2] * p[2] + m[r][3];
+		}
+
+		let w = m[3][0] * p[0] + m[3][1] * p[1] + m[3][2] * p[2] + m[3][3];
+		if w != 1.0 {
+			result /= w;
+		}
+		result
+	}
+
+	pub fn transform_vector(&self, v: &Vec3,) -> Vec3 { self.linear() * *v }
+
+	/// See `Mat3::transform_normal`, translation does not affect normals.
+	pub fn transform_normal(&self, n: &Vec3,) -> Option<Vec3,> { self.linear().transform_normal(n,) }
+}
+
+impl From<Mat3,> for Mat4 {
+	fn from(matrix: Mat3,) -> Self {
+		let mut result = Mat4::identity();
+		for r in 0 .. 3 {
+			result.m[r][.. 3].copy_from_slice(&matrix.m[r],);
+		}
+		result
+	}
+}
+
+impl From<Quat,> for Mat4 {
+	fn from(q: Quat,) -> Self { Mat4::from(Mat3::from(q,),) }
+}
+
+impl Mul for Mat4 {
+	type Output = Mat4;
+
+	fn mul(self, rhs: Mat4,) -> Self::Output {
+		let mut matrix = Mat4::new([[0.0; 4]; 4],);
+		for r in 0 .. 4 {
+			for c in 0 .. 4 {
+				matrix.m[r][c] = (0 .. 4).map(|k| self.m[r][k] * rhs.m[k][c],).sum();
+			}
+		}
+		matrix
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_close(a: &Mat4, b: &Mat4,) {
+		for r in 0 .. 4 {
+			for c in 0 .. 4 {
+				assert!(
+					(a.m[r][c] - b.m[r][c]).abs() < 1e-9,
+					"{:?} != {:?}",
+					a.m,
+					b.m
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn inverse_undoes_an_affine_transform() {
+		let matrix = Mat4::translation(Vec3::new(1.0, -2.0, 3.0,),)
+			* Mat4::rotation(Vec3::new(1.0, 1.0, 0.0,), 37.0,)
+			* Mat4::scaling(Vec3::new(2.0, 0.5, 3.0,),);
+		let inverse = matrix.inverse().unwrap();
+
+		assert_close(&(matrix * inverse), &Mat4::identity(),);
+		assert_close(&(inverse * matrix), &Mat4::identity(),);
+		assert_close(
+			&Mat4::from(matrix.linear().inverse().unwrap(),),
+			&Mat4::from(inverse.linear(),),
+		);
+	}
+
+	#[test]
+	fn inverse_needs_pivoting() {
+		// A permutation has zeros on its diagonal, elimination without row swaps fails on it.
+		let matrix = Mat4::new([
+			[0.0, 1.0, 0.0, 0.0,],
+			[0.0, 0.0, 1.0, 0.0,],
+			[1.0, 0.0, 0.0, 0.0,],
+			[0.0, 0.0, 0.0, 1.0,],
+		],);
+		assert_close(&matrix.inverse().unwrap(), &matrix.transpose(),);
+	}
+
+	#[test]
+	fn singular_matrices_have_no_inverse() {
+		assert!(
+			Mat4::scaling(Vec3::new(1.0, 0.0, 1.0,),)
+				.inverse()
+				.is_none()
+		);
+		assert!(
+			Mat3::scaling(Vec3::new(1.0, 1.0, 0.0,),)
+				.inverse()
+				.is_none()
+		);
+	}
+
+	#[test]
+	fn degenerate_frames_have_no_inverse() {
+		let p = Point3::new(1.0, 2.0, 3.0,);
+		assert!(Mat4::rotation(Vec3::default(), 30.0,).inverse().is_none());
+		assert!(Mat3::rotation(Vec3::default(), 30.0,).inverse().is_none());
+		assert!(
+			Mat4::look_at(p, p, Vec3::new(0.0, 1.0, 0.0,),)
+				.inverse()
+				.is_none()
+		);
+	}
+
+	#[test]
+	fn look_at_faces_the_target() {
+		let from = Point3::new(1.0, 2.0, 3.0,);
+		let matrix = Mat4::look_at(from, Point3::default(), Vec3::new(0.0, 1.0, 0.0,),);
+
+		let forward = matrix.transform_vector(&Vec3::new(0.0, 0.0, -1.0,),);
+		assert!((dot(&forward, &unit_vector(&-from,),) - 1.0).abs() < 1e-9);
+		assert!((matrix.transform_point(&Point3::default(),) - from).length() < 1e-9);
+	}
+
+	#[test]
+	fn quaternion_and_matrix_rotations_agree() {
+		let axis = Vec3::new(-1.0, 2.0, 0.5,);
+		let from_quat = Mat4::from(Quat::from_axis_angle(axis, 123.0,),);
+		assert_close(&from_quat, &Mat4::rotation(axis, 123.0,),);
+	}
+
+	#[test]
+	fn normals_stay_perpendicular_to_tangents() {
+		let matrix = Mat4::translation(Vec3::new(5.0, 0.0, -1.0,),)
+			* Mat4::rotation(Vec3::new(0.0, 0.0, 1.0,), 30.0,)
+			* Mat4::scaling(Vec3::new(3.0, 1.0, 0.5,),);
+		let normal = Vec3::new(1.0, 1.0, 1.0,);
+		let tangents = [Vec3::new(1.0, -1.0, 0.0,), Vec3::new(0.0, 1.0, -1.0,),];
+
+		let transformed = matrix.transform_normal(&normal,).unwrap();
+		for tangent in &tangents {
+			assert!(dot(&transformed, &matrix.transform_vector(tangent,),).abs() < 1e-9);
+		}
+		// Translation leaves normals alone, the linear part transforms them the same way.
+		let linear = matrix.linear().transform_normal(&normal,).unwrap();
+		assert!((linear - transformed).length() < 1e-12);
+		assert!(Mat4::scaling(Vec3::new(0.0, 1.0, 1.0,),).transform_normal(&normal,).is_none());
+	}
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::AABB,
+	bvh::{build_sah, flatten, LinearNode, traverse},
+	hittable::{HitRecord, Hittable},
+	material::Material,
+	ray::Ray,
+	triangle::{bounding_box, hit_record, intersect},
+	vec3::{Point3, Vec3},
+};
+
+const MAX_TRIANGLES_IN_LEAF: usize = 4;
+
+/// Triangles sharing vertex, normal and UV buffers, with a BVH over the triangles so that the
+/// whole mesh acts as a single object in the scene.
+pub struct TriangleMesh {
+	pub positions:   Vec<Point3,>,
+	pub normals:     Option<Vec<Vec3,>,>,
+	pub uvs:         Option<Vec<(f64, f64,),>,>,
+	pub indices:     Vec<[u32; 3],>,
+	pub mat_ptr:     Arc<dyn Material,>,
+	nodes:           Vec<LinearNode,>,
+	/// Where each triangle of `indices` was in the order `new` was given them.
+	order:           Vec<u32,>,
+	/// Whether hits report the triangle and its barycentric coordinates instead of UVs.
+	barycentric_uvs: bool,
+}
+
+impl TriangleMesh {
+	/// `indices` holds three vertex indices per triangle, in counter-clockwise order seen from the
+	/// front. The optional normal and UV buffers are indexed like `positions`.
+	pub fn new(
+		positions: Vec<Point3,>,
+		normals: Option<Vec<Vec3,>,>,
+		uvs: Option<Vec<(f64, f64,),>,>,
+		mut indices: Vec<[u32; 3],>,
+		mat_ptr: Arc<dyn Material,>,
+	) -> Self {
+		assert!(
+			normals.iter().all(|n| n.len() == positions.len()),
+			"TriangleMesh needs one normal per vertex"
+		);
+		assert!(
+			uvs.iter().all(|uv| uv.len() == positions.len()),
+			"TriangleMesh needs one UV per vertex"
+		);
+		assert!(
+			indices
+				.iter()
+				.flatten()
+				.all(|&i| (i as usize) < positions.len()),
+			"TriangleMesh index out of range"
+		);
+
+		let mut nodes = Vec::new();
+		let mut order = Vec::new();
+		if !indices.is_empty() {
+			let boxes: Vec<_,> = indices
+				.iter()
+				.map(|triangle| triangle_box(&positions, triangle,),)
+				.collect();
+			let (root, sorted,) = build_sah(&boxes, MAX_TRIANGLES_IN_LEAF,);
+			indices = sorted.iter().map(|&i| indices[i],).collect();
+			order = sorted.iter().map(|&i| i as u32,).collect();
+			flatten(&root, &mut nodes,);
+		}
+
+		TriangleMesh {
+			positions,
+			normals,
+			uvs,
+			indices,
+			mat_ptr,
+			nodes,
+			order,
+			barycentric_uvs: false,
+		}
+	}
+
+	/// Makes hits report `u = 2i + b1` and `v = b2` in place of the UV buffer, for triangle `i`
+	/// of the `indices` given to `new` and the barycentric coordinates `b1` and `b2` of the hit.
+	/// This is the layout `VertexColorTexture` reads.
+	pub fn with_barycentric_uvs(mut self,) -> Self {
+		self.uvs = None;
+		self.barycentric_uvs = true;
+		self
+	}
+
+	#[inline]
+	fn vertices<T: Copy,>(buffer: &[T], triangle: &[u32; 3],) -> [T; 3] {
+		[
+			buffer[triangle[0] as usize],
+			buffer[triangle[1] as usize],
+			buffer[triangle[2] as usize],
+		]
+	}
+}
+
+fn triangle_box(positions: &[Point3], triangle: &[u32; 3],) -> AABB {
+	bounding_box(&TriangleMesh::vertices(positions, triangle,),)
+}
+
+impl Hittable for TriangleMesh {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let mut closest = None;
+
+		traverse(&self.nodes, r, t_min, t_max, |range, mut closest_so_far| {
+			for i in range {
+				let vertices = Self::vertices(&self.positions, &self.indices[i],);
+				if let Some((t, b1, b2,),) = intersect(r, &vertices, t_min, closest_so_far,) {
+					closest_so_far = t;
+					closest = Some((i, t, b1, b2,),);
+				}
+			}
+			closest_so_far
+		},);
+
+		closest.map(|(i, t, b1, b2,)| {
+			let triangle = &self.indices[i];
+			let mut rec = hit_record(
+				r,
+				t,
+				(b1, b2,),
+				&Self::vertices(&self.positions, triangle,),
+				self.normals
+					.as_ref()
+					.map(|n| Self::vertices(n, triangle,),)
+					.as_ref(),
+				self.uvs
+					.as_ref()
+					.map(|uv| Self::vertices(uv, triangle,),)
+					.as_ref(),
+				&self.mat_ptr,
+			);
+			// Without UVs the hit already reports `b1` and `b2`.
+			if self.barycentric_uvs {
+				rec.u += 2.0 * self.order[i] as f64;
+			}
+			rec
+		},)
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> {
+		self.nodes.first().map(|root| root.aabb.clone(),)
+	}
+}
@@ -3,7 +3,7 @@ use crate::hittable::{HitRecord, Hittable};
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::{dot, Point3, Vec3};
-use std::rc::Rc;
+use std::sync::Arc;
 
 pub struct MovingSphere {
 	pub center0: Point3,
@@ -11,7 +11,7 @@ pub struct MovingSphere {
 	pub time0: f64,
 	pub time1: f64,
 	pub radius: f64,
-	pub mat_ptr: Rc<dyn Material>,
+	pub mat_ptr: Arc<dyn Material>,
 }
 
 impl MovingSphere {
@@ -21,7 +21,7 @@ impl MovingSphere {
 		time0: f64,
 		time1: f64,
 		radius: f64,
-		mat_ptr: Rc<dyn Material>,
+		mat_ptr: Arc<dyn Material>,
 	) -> Self {
 		MovingSphere {
 			center0,
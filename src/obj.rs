@@ -0,0 +1,430 @@
+use std::{
+	collections::{HashMap, HashSet},
+	fmt::{Display, Formatter},
+	fs::read_to_string,
+	io,
+	path::{Path, PathBuf},
+	str::SplitWhitespace,
+	sync::Arc,
+};
+
+use crate::{
+	hittable_list::HittableList,
+	material::{Dielectric, DiffuseLight, Lambertian, Material},
+	mesh::TriangleMesh,
+	texture::ImageTexture,
+	vec3::{Color, Point3, Vec3},
+};
+
+/// Everything that can go wrong while loading an OBJ file or one of its material libraries.
+#[derive(Debug,)]
+pub enum ObjError {
+	Io(PathBuf, io::Error,),
+	Parse {
+		file:    PathBuf,
+		line:    usize,
+		message: String,
+	},
+}
+
+impl Display for ObjError {
+	fn fmt(&self, f: &mut Formatter<'_,>,) -> std::fmt::Result {
+		match self {
+			ObjError::Io(file, e,) => write!(f, "{}: {}", file.display(), e),
+			ObjError::Parse {
+				file,
+				line,
+				message,
+			} => write!(f, "{}:{}: {}", file.display(), line, message),
+		}
+	}
+}
+
+/// Reads a file and hands its lines, numbered from 1 and stripped of comments, to `parse_line`.
+/// Errors returned as plain messages get the file name and line number attached.
+fn parse_lines<F,>(path: &Path, mut parse_line: F,) -> Result<(), ObjError,>
+where F: FnMut(&str, SplitWhitespace,) -> Result<(), String,> {
+	let source = read_to_string(path,).map_err(|e| ObjError::Io(path.to_path_buf(), e,),)?;
+
+	for (number, line,) in source.lines().enumerate() {
+		let line = line.split('#',).next().unwrap_or_default();
+		let mut tokens = line.split_whitespace();
+		if let Some(keyword,) = tokens.next() {
+			parse_line(keyword, tokens,).map_err(|message| ObjError::Parse {
+				file: path.to_path_buf(),
+				line: number + 1,
+				message,
+			},)?;
+		}
+	}
+
+	Ok((),)
+}
+
+fn parse_floats<const N: usize,>(
+	keyword: &str,
+	tokens: SplitWhitespace,
+	required: usize,
+) -> Result<[f64; N], String,> {
+	let mut values = [0.0; N];
+	let mut count = 0;
+
+	for token in tokens {
+		if count == N {
+			return Err(format!("`{}` takes at most {} values", keyword, N),);
+		}
+		values[count] = token
+			.parse()
+			.map_err(|_| format!("`{}` is not a number in `{}`", token, keyword),)?;
+		count += 1;
+	}
+
+	if count < required {
+		return Err(format!("`{}` needs at least {} values, got {}", keyword, required, count),);
+	}
+
+	Ok(values,)
+}
+
+#[derive(Default,)]
+struct MtlMaterial {
+	kd:     Option<Color,>,
+	ke:     Option<Color,>,
+	ni:     Option<f64,>,
+	d:      Option<f64,>,
+	map_kd: Option<PathBuf,>,
+}
+
+impl MtlMaterial {
+	/// Emissive materials become lights, transparent ones glass, and everything else is diffuse.
+	fn to_material(&self,) -> Arc<dyn Material,> {
+		if let Some(ke,) = self.ke.filter(|ke| !ke.near_zero(),) {
+			return Arc::new(DiffuseLight::from(ke,),);
+		}
+		if matches!(self.d, Some(d) if d < 1.0) {
+			return Arc::new(Dielectric::new(self.ni.unwrap_or(1.5,),),);
+		}
+		if let Some(map_kd,) = &self.map_kd {
+			return Arc::new(Lambertian::from(Arc::new(ImageTexture::new(
+				&map_kd.to_string_lossy(),
+			),),),);
+		}
+
+		Arc::new(Lambertian::new(
+			self.kd.unwrap_or_else(|| Color::new(0.8, 0.8, 0.8,),),
+		),)
+	}
+}
+
+fn load_mtl(path: &Path, materials: &mut HashMap<String, MtlMaterial,>,) -> Result<(), ObjError,> {
+	let dir = path.parent().unwrap_or_else(|| Path::new("",),);
+	let mut current: Option<String,> = None;
+
+	parse_lines(path, |keyword, mut tokens| {
+		if keyword == "newmtl" {
+			let name = tokens.next().ok_or("`newmtl` needs a name",)?;
+			materials.insert(name.to_string(), MtlMaterial::default(),);
+			current = Some(name.to_string(),);
+			return Ok((),);
+		}
+
+		let material = match &current {
+			Some(name,) => materials.get_mut(name,).unwrap(),
+			None => return Err(format!("`{}` before the first `newmtl`", keyword),),
+		};
+
+		match keyword {
+			"Kd" => material.kd = Some(Vec3 { e: parse_floats(keyword, tokens, 3,)?, },),
+			"Ke" => material.ke = Some(Vec3 { e: parse_floats(keyword, tokens, 3,)?, },),
+			"Ni" => material.ni = Some(parse_floats::<1,>(keyword, tokens, 1,)?[0],),
+			"d" => material.d = Some(parse_floats::<1,>(keyword, tokens, 1,)?[0],),
+			"Tr" => material.d = Some(1.0 - parse_floats::<1,>(keyword, tokens, 1,)?[0],),
+			"map_Kd" => {
+				// Options such as `-s 1 1 1` may precede the file name, which always comes last.
+				let file = tokens.last().ok_or("`map_Kd` needs a file name",)?;
+				material.map_kd = Some(dir.join(file,),);
+			},
+			_ => {},
+		}
+
+		Ok((),)
+	},)
+}
+
+/// A vertex of a face, as 0-based indices into the position, UV and normal lists.
+type FaceVertex = (usize, Option<usize,>, Option<usize,>,);
+
+fn parse_index(token: &str, count: usize,) -> Result<usize, String,> {
+	let index: i64 = token.parse().map_err(|_| format!("`{}` is not an index", token),)?;
+	let resolved = if index < 0 {
+		count as i64 + index
+	} else {
+		index - 1
+	};
+
+	if index == 0 || resolved < 0 || resolved >= count as i64 {
+		return Err(format!("index {} out of range, {} defined so far", index, count),);
+	}
+
+	Ok(resolved as usize,)
+}
+
+fn parse_face_vertex(
+	token: &str,
+	positions: usize,
+	uvs: usize,
+	normals: usize,
+) -> Result<FaceVertex, String,> {
+	let mut parts = token.split('/',);
+	let v = parse_index(parts.next().unwrap_or_default(), positions,)?;
+	let vt = match parts.next() {
+		None | Some("",) => None,
+		Some(vt,) => Some(parse_index(vt, uvs,)?,),
+	};
+	let vn = match parts.next() {
+		None | Some("",) => None,
+		Some(vn,) => Some(parse_index(vn, normals,)?,),
+	};
+
+	if parts.next().is_some() {
+		return Err(format!("malformed face vertex `{}`", token),);
+	}
+
+	Ok((v, vt, vn,),)
+}
+
+/// The triangles using one material, with OBJ's separate position/UV/normal indices merged into
+/// the single index per vertex that `TriangleMesh` uses.
+#[derive(Default,)]
+struct Group {
+	vertices:  Vec<FaceVertex,>,
+	lookup:    HashMap<FaceVertex, u32,>,
+	triangles: Vec<[u32; 3],>,
+}
+
+impl Group {
+	fn vertex(&mut self, vertex: FaceVertex,) -> u32 {
+		let vertices = &mut self.vertices;
+		*self.lookup.entry(vertex,).or_insert_with(|| {
+			vertices.push(vertex,);
+			(vertices.len() - 1) as u32
+		},)
+	}
+
+	fn into_mesh(
+		self,
+		positions: &[Point3],
+		uvs: &[(f64, f64,)],
+		normals: &[Vec3],
+		mat_ptr: Arc<dyn Material,>,
+	) -> TriangleMesh {
+		let has_uvs = self.vertices.iter().all(|(_, vt, _,)| vt.is_some(),);
+		let has_normals = self.vertices.iter().all(|(_, _, vn,)| vn.is_some(),);
+
+		TriangleMesh::new(
+			self.vertices.iter().map(|&(v, _, _,)| positions[v],).collect(),
+			if has_normals {
+				Some(self.vertices.iter().map(|&(_, _, vn,)| normals[vn.unwrap()],).collect(),)
+			} else {
+				None
+			},
+			if has_uvs {
+				Some(self.vertices.iter().map(|&(_, vt, _,)| uvs[vt.unwrap()],).collect(),)
+			} else {
+				None
+			},
+			self.triangles,
+			mat_ptr,
+		)
+	}
+}
+
+/// Loads a Wavefront OBJ file as one `TriangleMesh` per material. Materials come from the MTL
+/// libraries it references: `Kd` and `map_Kd` give a `Lambertian`, `Ke` a `DiffuseLight` and a
+/// dissolve `d` below 1 a `Dielectric` with index `Ni`. Faces without a material use
+/// `default_material`, polygons are split into triangle fans. Unsupported statements are skipped
+/// with a warning.
+pub fn load(path: &Path, default_material: Arc<dyn Material,>,) -> Result<HittableList, ObjError,> {
+	let dir = path.parent().unwrap_or_else(|| Path::new("",),);
+
+	let mut positions = Vec::new();
+	let mut uvs = Vec::new();
+	let mut normals = Vec::new();
+	let mut libraries = HashMap::new();
+	let mut groups: Vec<(Option<String,>, Group,),> = vec![(None, Group::default(),)];
+	let mut current = 0;
+	let mut skipped = HashSet::new();
+
+	parse_lines(path, |keyword, mut tokens| {
+		match keyword {
+			"v" => positions.push(Vec3 {
+				e: parse_floats::<4,>(keyword, tokens, 3,)
+					.map(|[x, y, z, _,]| [x, y, z,],)?,
+			},),
+			"vt" => uvs.push(
+				parse_floats::<3,>(keyword, tokens, 1,).map(|[u, v, _,]| (u, v,),)?,
+			),
+			"vn" => normals.push(Vec3 { e: parse_floats(keyword, tokens, 3,)?, },),
+			"f" => {
+				let face = tokens
+					.map(|token| parse_face_vertex(token, positions.len(), uvs.len(), normals.len(),),)
+					.collect::<Result<Vec<_,>, _,>>()?;
+				if face.len() < 3 {
+					return Err(format!("a face needs at least 3 vertices, got {}", face.len()),);
+				}
+
+				let group = &mut groups[current].1;
+				let first = group.vertex(face[0],);
+				for pair in face[1 ..].windows(2,) {
+					let triangle = [first, group.vertex(pair[0],), group.vertex(pair[1],),];
+					group.triangles.push(triangle,);
+				}
+			},
+			"usemtl" => {
+				let name = tokens.next().ok_or("`usemtl` needs a material name",)?;
+				current = match groups.iter().position(|(n, _,)| n.as_deref() == Some(name,),) {
+					Some(index,) => index,
+					None => {
+						groups.push((Some(name.to_string(),), Group::default(),),);
+						groups.len() - 1
+					},
+				};
+			},
+			"mtllib" => {
+				for library in tokens {
+					load_mtl(&dir.join(library,), &mut libraries,).map_err(|e| e.to_string(),)?;
+				}
+			},
+			"vp" | "o" | "g" | "s" | "l" | "p" | "cstype" | "deg" | "curv" | "surf" | "parm"
+			| "end" => {},
+			// Other statements, like `mg`, `usemap` or `bevel`, only affect display or free-form
+			// geometry, and exporters write some of them routinely.
+			_ =>
+				if skipped.insert(keyword.to_string(),) {
+					eprintln!("{}: skipping unsupported statement `{}`", path.display(), keyword);
+				},
+		}
+
+		Ok((),)
+	},)?;
+
+	let mut materials = HashMap::new();
+	let mut list = HittableList::default();
+
+	for (name, group,) in groups {
+		if group.triangles.is_empty() {
+			continue;
+		}
+
+		let mat_ptr = match &name {
+			None => default_material.clone(),
+			Some(name,) => match libraries.get(name,) {
+				Some(material,) => materials
+					.entry(name.clone(),)
+					.or_insert_with(|| material.to_material(),)
+					.clone(),
+				None => {
+					eprintln!("{}: unknown material `{}`", path.display(), name);
+					default_material.clone()
+				},
+			},
+		};
+
+		list.add(Arc::new(group.into_mesh(&positions, &uvs, &normals, mat_ptr,),),);
+	}
+
+	Ok(list,)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ray::Ray, util::temp_file};
+
+	fn load_source(name: &str, source: &str,) -> Result<HittableList, ObjError,> {
+		let path = temp_file(name, source.as_bytes(),);
+		let result = load(&path, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5,),),),);
+		std::fs::remove_file(path,).unwrap();
+		result
+	}
+
+	fn error_line(result: Result<HittableList, ObjError,>,) -> usize {
+		match result {
+			Err(ObjError::Parse { line, .. },) => line,
+			Err(e,) => panic!("unexpected error {}", e),
+			Ok(_,) => panic!("loaded a broken file"),
+		}
+	}
+
+	#[test]
+	fn indices_count_from_one_or_back_from_the_end() {
+		assert_eq!(parse_index("1", 3,), Ok(0,));
+		assert_eq!(parse_index("3", 3,), Ok(2,));
+		assert_eq!(parse_index("-1", 3,), Ok(2,));
+		assert_eq!(parse_index("-3", 3,), Ok(0,));
+	}
+
+	#[test]
+	fn out_of_range_indices_are_rejected() {
+		for token in ["0", "4", "-4", "99999999999999999999", "x", "",] {
+			assert!(parse_index(token, 3,).is_err(), "accepted `{}`", token);
+		}
+	}
+
+	#[test]
+	fn face_vertices_may_skip_uvs() {
+		assert_eq!(parse_face_vertex("2", 3, 0, 0,), Ok((1, None, None,),));
+		assert_eq!(parse_face_vertex("-1//1", 3, 0, 1,), Ok((2, None, Some(0,),),));
+		assert_eq!(parse_face_vertex("1/2/-1", 3, 2, 4,), Ok((0, Some(1,), Some(3,),),));
+		assert!(parse_face_vertex("1/1/1/1", 3, 1, 1,).is_err());
+		assert!(parse_face_vertex("1/2", 3, 1, 0,).is_err());
+	}
+
+	#[test]
+	fn loads_a_quad_with_relative_indices() {
+		let list = load_source(
+			"quad.obj",
+			"v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n# two triangles\nf -4 -3 -2 -1\n",
+		)
+		.unwrap();
+		assert_eq!(list.objects.len(), 1);
+
+		let ray = Ray::new(Point3::new(0.9, 0.8, 1.0,), Vec3::new(0.0, 0.0, -1.0,), 0.0,);
+		let rec = list.objects[0].hit(&ray, 0.001, f64::INFINITY,).unwrap();
+		assert!((rec.t - 1.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn skips_unsupported_statements() {
+		let source = "mg 1 0.5\nusemap map\nv 0 0 0\nv 1 0 0\nv 0 1 0\nlod 2\nbevel on\nf 1 2 3\n";
+		assert_eq!(load_source("skipped.obj", source,).unwrap().objects.len(), 1);
+	}
+
+	#[test]
+	fn malformed_files_fail_on_the_right_line() {
+		let cases = [
+			("forward.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 4\n", 4,),
+			("backward.obj", "v 0 0 0\nv 1 0 0\nf -1 -2 -3\n", 3,),
+			("zero.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 0 1 2\n", 4,),
+			("short.obj", "v 0 0\n", 1,),
+			("long.obj", "v 0 0 0 1 2\n", 1,),
+			("number.obj", "v 0 0 zero\n", 1,),
+			("line.obj", "v 0 0 0\nv 1 0 0\n\nf 1 2\n", 4,),
+			("uv.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nf 1/1 2/2 3/1\n", 5,),
+			("usemtl.obj", "usemtl\n", 1,),
+		];
+
+		for (name, source, line,) in cases {
+			assert_eq!(error_line(load_source(name, source,),), line, "{}", name);
+		}
+	}
+
+	#[test]
+	fn missing_files_are_io_errors() {
+		let result = load(
+			Path::new("does/not/exist.obj",),
+			Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5,),),),
+		);
+		assert!(matches!(result, Err(ObjError::Io(..))));
+	}
+}
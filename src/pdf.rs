@@ -0,0 +1,87 @@
+use std::f64::consts::PI;
+
+use crate::{
+	onb::Onb,
+	sampler::Sampler,
+	vec3::{dot, sample_cosine_direction, sample_unit_vector, unit_vector, Vec3},
+};
+
+/// A probability density over directions, which can also draw directions from itself.
+pub trait Pdf {
+	/// The density of `direction`, per unit solid angle.
+	fn value(&self, direction: &Vec3,) -> f64;
+
+	fn generate(&self, sampler: &mut dyn Sampler,) -> Vec3;
+}
+
+/// Directions around `w` with a density proportional to their cosine with it, which matches the
+/// light a diffuse surface scatters.
+pub struct CosinePdf {
+	uvw: Onb,
+}
+
+impl CosinePdf {
+	pub fn new(w: &Vec3,) -> Self {
+		CosinePdf {
+			uvw: Onb::build_from_w(w,),
+		}
+	}
+}
+
+impl Pdf for CosinePdf {
+	fn value(&self, direction: &Vec3,) -> f64 {
+		let cosine = dot(&unit_vector(direction,), &self.uvw.w(),);
+		(cosine / PI).max(0.0,)
+	}
+
+	fn generate(&self, sampler: &mut dyn Sampler,) -> Vec3 {
+		self.uvw.local(&sample_cosine_direction(sampler.get_2d(),),)
+	}
+}
+
+/// All directions equally likely.
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+	fn value(&self, _direction: &Vec3,) -> f64 { 1.0 / (4.0 * PI) }
+
+	fn generate(&self, sampler: &mut dyn Sampler,) -> Vec3 { sample_unit_vector(sampler.get_2d(),) }
+}
+
+/// Directions around `w` with a density proportional to their cosine with it raised to
+/// `exponent`, the lobe of a glossy reflection. Larger exponents give narrower lobes.
+pub struct PhongPdf {
+	uvw:      Onb,
+	exponent: f64,
+}
+
+impl PhongPdf {
+	pub fn new(w: &Vec3, exponent: f64,) -> Self {
+		PhongPdf {
+			uvw: Onb::build_from_w(w,),
+			exponent,
+		}
+	}
+}
+
+impl Pdf for PhongPdf {
+	fn value(&self, direction: &Vec3,) -> f64 {
+		let cosine = dot(&unit_vector(direction,), &self.uvw.w(),);
+		if cosine <= 0.0 {
+			return 0.0;
+		}
+		(self.exponent + 1.0) / (2.0 * PI) * cosine.powf(self.exponent,)
+	}
+
+	fn generate(&self, sampler: &mut dyn Sampler,) -> Vec3 {
+		let (u0, u1,) = sampler.get_2d();
+		let cos_theta = u0.powf(1.0 / (self.exponent + 1.0),);
+		let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+		let phi = 2.0 * PI * u1;
+		self.uvw.local(&Vec3::new(
+			phi.cos() * sin_theta,
+			phi.sin() * sin_theta,
+			cos_theta,
+		),)
+	}
+}
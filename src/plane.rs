@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::AABB,
+	hittable::{HitRecord, Hittable},
+	material::Material,
+	onb::Onb,
+	ray::Ray,
+	vec3::{dot, Point3, Vec3},
+};
+
+/// An infinite plane through `point`. UVs are the plain coordinates along two axes in the
+/// plane, so textures repeat instead of stretching. It has no bounding box and is kept out of
+/// any BVH.
+pub struct Plane {
+	point:   Point3,
+	mat_ptr: Arc<dyn Material,>,
+	onb:     Onb,
+}
+
+impl Plane {
+	pub fn new(point: Point3, normal: Vec3, mat_ptr: Arc<dyn Material,>,) -> Self {
+		Plane {
+			point,
+			mat_ptr,
+			onb: Onb::build_from_w(&normal,),
+		}
+	}
+}
+
+impl Hittable for Plane {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let normal = self.onb.w();
+		let denom = dot(&normal, &r.direction(),);
+		if denom.abs() < 1e-8 {
+			return None;
+		}
+
+		let t = dot(&normal, &(self.point - r.origin()),) / denom;
+		if t < t_min || t > t_max {
+			return None;
+		}
+
+		let p = r.at(t,);
+		let local = self.onb.to_local(&(p - self.point),);
+
+		let mut rec = HitRecord {
+			p,
+			normal: Default::default(),
+			mat_ptr: self.mat_ptr.clone(),
+			t,
+			u: local.x(),
+			v: local.y(),
+			front_face: false,
+		};
+		rec.set_face_normal(r, &normal,);
+
+		Some(rec,)
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> { None }
+}
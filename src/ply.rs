@@ -0,0 +1,499 @@
+use std::{
+	convert::TryInto,
+	fmt::{Display, Formatter},
+	fs::read,
+	io,
+	path::{Path, PathBuf},
+	str::SplitAsciiWhitespace,
+	sync::Arc,
+};
+
+use crate::{
+	material::Material,
+	mesh::TriangleMesh,
+	texture::VertexColorTexture,
+	vec3::{Color, Point3, Vec3},
+};
+
+/// Everything that can go wrong while loading a PLY file.
+#[derive(Debug,)]
+pub enum PlyError {
+	Io(PathBuf, io::Error,),
+	Parse(PathBuf, String,),
+}
+
+impl Display for PlyError {
+	fn fmt(&self, f: &mut Formatter<'_,>,) -> std::fmt::Result {
+		match self {
+			PlyError::Io(file, e,) => write!(f, "{}: {}", file.display(), e),
+			PlyError::Parse(file, message,) => write!(f, "{}: {}", file.display(), message),
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq,)]
+enum Scalar {
+	I8,
+	U8,
+	I16,
+	U16,
+	I32,
+	U32,
+	F32,
+	F64,
+}
+
+impl Scalar {
+	fn parse(name: &str,) -> Result<Self, String,> {
+		Ok(match name {
+			"char" | "int8" => Scalar::I8,
+			"uchar" | "uint8" => Scalar::U8,
+			"short" | "int16" => Scalar::I16,
+			"ushort" | "uint16" => Scalar::U16,
+			"int" | "int32" => Scalar::I32,
+			"uint" | "uint32" => Scalar::U32,
+			"float" | "float32" => Scalar::F32,
+			"double" | "float64" => Scalar::F64,
+			_ => return Err(format!("unknown property type `{}`", name),),
+		},)
+	}
+
+	/// The value that stands for full intensity when this type holds a color channel.
+	fn color_scale(self,) -> f64 {
+		match self {
+			Scalar::U8 => 255.0,
+			Scalar::U16 => 65535.0,
+			_ => 1.0,
+		}
+	}
+}
+
+enum Property {
+	Scalar(Scalar, String,),
+	List(Scalar, Scalar, String,),
+}
+
+struct Element {
+	name:       String,
+	count:      usize,
+	properties: Vec<Property,>,
+}
+
+/// The data following the header, read one scalar at a time.
+enum Body<'a,> {
+	Ascii(SplitAsciiWhitespace<'a,>,),
+	Binary { bytes: &'a [u8], big_endian: bool, },
+}
+
+macro_rules! read_binary {
+	($bytes:expr, $big_endian:expr, $type:ty) => {{
+		const SIZE: usize = std::mem::size_of::<$type,>();
+		if $bytes.len() < SIZE {
+			return Err("unexpected end of file".to_string(),);
+		}
+		let (value, rest,) = $bytes.split_at(SIZE,);
+		*$bytes = rest;
+		let value = value.try_into().unwrap();
+		(if $big_endian {
+			<$type>::from_be_bytes(value,)
+		} else {
+			<$type>::from_le_bytes(value,)
+		}) as f64
+	}};
+}
+
+impl Body<'_,> {
+	fn read(&mut self, scalar: Scalar,) -> Result<f64, String,> {
+		match self {
+			Body::Ascii(tokens,) => {
+				let token = tokens.next().ok_or("unexpected end of file",)?;
+				token.parse().map_err(|_| format!("`{}` is not a number", token),)
+			},
+			Body::Binary { bytes, big_endian, } => Ok(match scalar {
+				Scalar::I8 => read_binary!(bytes, *big_endian, i8),
+				Scalar::U8 => read_binary!(bytes, *big_endian, u8),
+				Scalar::I16 => read_binary!(bytes, *big_endian, i16),
+				Scalar::U16 => read_binary!(bytes, *big_endian, u16),
+				Scalar::I32 => read_binary!(bytes, *big_endian, i32),
+				Scalar::U32 => read_binary!(bytes, *big_endian, u32),
+				Scalar::F32 => read_binary!(bytes, *big_endian, f32),
+				Scalar::F64 => read_binary!(bytes, *big_endian, f64),
+			},),
+		}
+	}
+}
+
+/// Splits the file into its elements and the body, which starts right after `end_header`.
+fn parse_header(bytes: &[u8],) -> Result<(Vec<Element,>, Body<'_,>,), String,> {
+	let end = bytes
+		.windows(b"end_header".len(),)
+		.position(|window| window == b"end_header",)
+		.ok_or("missing `end_header`",)?;
+	let body_start = bytes[end ..]
+		.iter()
+		.position(|&byte| byte == b'\n',)
+		.map_or(bytes.len(), |newline| end + newline + 1,);
+	let header = std::str::from_utf8(&bytes[.. end],).map_err(|_| "header is not valid text",)?;
+
+	let mut lines = header.lines();
+	if lines.next().map(str::trim_end,) != Some("ply",) {
+		return Err("not a PLY file".to_string(),);
+	}
+
+	let mut format = None;
+	let mut elements: Vec<Element,> = Vec::new();
+
+	for line in lines {
+		let mut tokens = line.split_whitespace();
+		match tokens.next() {
+			Some("format",) => format = tokens.next().map(str::to_string,),
+			Some("element",) => {
+				let name = tokens.next().ok_or("`element` needs a name",)?;
+				let count = tokens
+					.next()
+					.and_then(|count| count.parse().ok(),)
+					.ok_or_else(|| format!("element `{}` needs a count", name),)?;
+				elements.push(Element {
+					name: name.to_string(),
+					count,
+					properties: Vec::new(),
+				},);
+			},
+			Some("property",) => {
+				let element = elements.last_mut().ok_or("`property` before the first `element`",)?;
+				let property = match tokens.next() {
+					Some("list",) => {
+						let count = Scalar::parse(tokens.next().unwrap_or_default(),)?;
+						let item = Scalar::parse(tokens.next().unwrap_or_default(),)?;
+						Property::List(count, item, tokens.next().unwrap_or_default().to_string(),)
+					},
+					Some(scalar,) => {
+						Property::Scalar(Scalar::parse(scalar,)?, tokens.next().unwrap_or_default().to_string(),)
+					},
+					None => return Err("`property` needs a type".to_string(),),
+				};
+				element.properties.push(property,);
+			},
+			Some("comment" | "obj_info",) | None => {},
+			Some(keyword,) => return Err(format!("unknown header line `{}`", keyword),),
+		}
+	}
+
+	let body = &bytes[body_start ..];
+	let body = match format.as_deref() {
+		Some("ascii",) => Body::Ascii(
+			std::str::from_utf8(body,)
+				.map_err(|_| "ASCII body is not valid text",)?
+				.split_ascii_whitespace(),
+		),
+		Some("binary_little_endian",) => Body::Binary {
+			bytes:      body,
+			big_endian: false,
+		},
+		Some("binary_big_endian",) => Body::Binary {
+			bytes:      body,
+			big_endian: true,
+		},
+		Some(format,) => return Err(format!("unknown format `{}`", format),),
+		None => return Err("missing `format`".to_string(),),
+	};
+
+	Ok((elements, body,),)
+}
+
+/// A triangulated PLY model. The optional buffers are indexed like `positions`.
+pub struct Ply {
+	pub positions: Vec<Point3,>,
+	pub normals:   Option<Vec<Vec3,>,>,
+	pub uvs:       Option<Vec<(f64, f64,),>,>,
+	pub colors:    Option<Vec<Color,>,>,
+	pub indices:   Vec<[u32; 3],>,
+}
+
+impl Ply {
+	/// Loads an ASCII or binary PLY file. Vertices may carry normals (`nx`, `ny`, `nz`), UVs
+	/// (`u`/`v`, `s`/`t` or `texture_u`/`texture_v`) and colors (`red`, `green`, `blue`), faces are
+	/// split into triangle fans and all other elements are skipped.
+	pub fn load(path: &Path,) -> Result<Ply, PlyError,> {
+		let bytes = read(path,).map_err(|e| PlyError::Io(path.to_path_buf(), e,),)?;
+		Ply::parse(&bytes,).map_err(|message| PlyError::Parse(path.to_path_buf(), message,),)
+	}
+
+	fn parse(bytes: &[u8],) -> Result<Ply, String,> {
+		let (elements, mut body,) = parse_header(bytes,)?;
+
+		let mut ply = Ply {
+			positions: Vec::new(),
+			normals:   None,
+			uvs:       None,
+			colors:    None,
+			indices:   Vec::new(),
+		};
+
+		for element in &elements {
+			let slot = |names: &[&str]| {
+				element.properties.iter().position(
+					|property| matches!(property, Property::Scalar(_, name) if names.contains(&name.as_str())),
+				)
+			};
+			let all = |slots: [Option<usize,>; 3]| -> Option<[usize; 3]> {
+				Some([slots[0]?, slots[1]?, slots[2]?,],)
+			};
+
+			let position = all([slot(&["x"],), slot(&["y"],), slot(&["z"],),],);
+			let normal = all([slot(&["nx"],), slot(&["ny"],), slot(&["nz"],),],);
+			let uv = slot(&["u", "s", "texture_u", "texture_s"],).zip(slot(&[
+				"v",
+				"t",
+				"texture_v",
+				"texture_t",
+			],),);
+			let color = all([slot(&["red"],), slot(&["green"],), slot(&["blue"],),],);
+			let face = element.properties.iter().position(|property| {
+				matches!(property, Property::List(_, _, name) if name == "vertex_indices" || name == "vertex_index")
+			},);
+
+			let is_vertex = element.name == "vertex";
+			let is_face = element.name == "face";
+			if is_vertex {
+				if position.is_none() {
+					return Err("vertices need `x`, `y` and `z`".to_string(),);
+				}
+				// The count comes from the header, a broken file could claim any number.
+				ply.normals = normal.map(|_| Vec::new(),);
+				ply.uvs = uv.map(|_| Vec::new(),);
+				ply.colors = color.map(|_| Vec::new(),);
+			}
+
+			let mut values = vec![0.0; element.properties.len()];
+			let mut face_indices = Vec::new();
+
+			for _ in 0 .. element.count {
+				for (i, property,) in element.properties.iter().enumerate() {
+					match property {
+						Property::Scalar(scalar, _,) => values[i] = body.read(*scalar,)?,
+						Property::List(count, item, _,) => {
+							let count = body.read(*count,)? as usize;
+							let keep = is_face && face == Some(i,);
+							face_indices.clear();
+							for _ in 0 .. count {
+								let index = body.read(*item,)?;
+								if keep {
+									if index < 0.0 {
+										return Err(format!("negative face index {}", index),);
+									}
+									face_indices.push(index as u32,);
+								}
+							}
+						},
+					}
+				}
+
+				if is_vertex {
+					let vec3 = |[x, y, z,]: [usize; 3]| Vec3::new(values[x], values[y], values[z],);
+					ply.positions.push(vec3(position.unwrap(),),);
+					if let (Some(normals,), Some(normal,),) = (&mut ply.normals, normal,) {
+						normals.push(vec3(normal,),);
+					}
+					if let (Some(uvs,), Some((u, v,),),) = (&mut ply.uvs, uv,) {
+						uvs.push((values[u], values[v],),);
+					}
+					if let (Some(colors,), Some(color,),) = (&mut ply.colors, color,) {
+						let scale = match &element.properties[color[0]] {
+							Property::Scalar(scalar, _,) => scalar.color_scale(),
+							Property::List(..,) => unreachable!(),
+						};
+						colors.push(vec3(color,) / scale,);
+					}
+				}
+
+				if is_face && face_indices.len() >= 3 {
+					for pair in face_indices[1 ..].windows(2,) {
+						ply.indices.push([face_indices[0], pair[0], pair[1],],);
+					}
+				}
+			}
+		}
+
+		if ply.indices.iter().flatten().any(|&i| i as usize >= ply.positions.len(),) {
+			return Err(format!("face index out of range, {} vertices", ply.positions.len()),);
+		}
+
+		Ok(ply,)
+	}
+
+	/// The vertex colors as a texture, for a mesh of these faces with barycentric UVs.
+	pub fn vertex_color_texture(&self,) -> Option<VertexColorTexture,> {
+		let colors = self.colors.clone()?;
+		Some(VertexColorTexture::new(colors, self.indices.clone(),),)
+	}
+
+	pub fn into_mesh(self, mat_ptr: Arc<dyn Material,>,) -> TriangleMesh {
+		TriangleMesh::new(self.positions, self.normals, self.uvs, self.indices, mat_ptr,)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{hittable::Hittable, material::Lambertian, ray::Ray, texture::Texture};
+
+	const HEADER: &str = "ply
+format ascii 1.0
+element vertex 4
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+	fn parse_ascii(body: &str,) -> Result<Ply, String,> {
+		Ply::parse(format!("{}{}", HEADER, body).as_bytes(),)
+	}
+
+	#[test]
+	fn splits_faces_into_fans() {
+		let ply = parse_ascii("0 0 0\n1 0 0\n1 1 0\n0 1 0\n4 0 1 2 3\n",).unwrap();
+		assert_eq!(ply.positions.len(), 4);
+		assert_eq!(ply.indices, vec![[0, 1, 2,], [0, 2, 3,]]);
+		assert!(ply.normals.is_none() && ply.uvs.is_none() && ply.colors.is_none());
+	}
+
+	#[test]
+	fn rejects_negative_and_out_of_range_indices() {
+		assert!(parse_ascii("0 0 0\n1 0 0\n1 1 0\n0 1 0\n3 0 1 -1\n").is_err());
+		assert!(parse_ascii("0 0 0\n1 0 0\n1 1 0\n0 1 0\n3 0 1 4\n").is_err());
+	}
+
+	#[test]
+	fn rejects_truncated_and_malformed_bodies() {
+		assert!(parse_ascii("0 0 0\n1 0 0\n1 1 0\n0 1 0\n3 0 1\n").is_err());
+		assert!(parse_ascii("0 0 0\n1 0 0\n1 1 0\n").is_err());
+		assert!(parse_ascii("0 0 0\n1 0 zero\n1 1 0\n0 1 0\n3 0 1 2\n").is_err());
+	}
+
+	#[test]
+	fn rejects_malformed_headers() {
+		let cases = [
+			"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n",
+			"obj\nformat ascii 1.0\nend_header\n",
+			"ply\nelement vertex 0\nend_header\n",
+			"ply\nformat ascii 1.0\nproperty float x\nend_header\n",
+			"ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n0\n",
+			"ply\nformat ascii 1.0\nelement vertex many\nend_header\n",
+			"ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n0\n",
+			"ply\nformat utf8 1.0\nelement vertex 0\nend_header\n",
+			"ply\nformat ascii 1.0\nvertex 1\nend_header\n",
+		];
+
+		for header in cases {
+			assert!(Ply::parse(header.as_bytes(),).is_err(), "accepted {:?}", header);
+		}
+	}
+
+	#[test]
+	fn huge_counts_fail_instead_of_allocating() {
+		let file = "ply
+format ascii 1.0
+element vertex 99999999999999999
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+end_header
+0 0 0 0 0 1
+";
+		assert!(Ply::parse(file.as_bytes(),).is_err());
+	}
+
+	#[test]
+	fn reads_binary_bodies_and_scales_colors() {
+		let mut bytes = b"ply
+format binary_big_endian 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar uint vertex_indices
+end_header
+"
+		.to_vec();
+		let vertices = [
+			([0.0f32, 0.0, 0.0,], [255, 0, 0,],),
+			([1.0, 0.0, 0.0,], [0, 255, 0,],),
+			([0.0, 1.0, 0.0,], [0, 0, 51,],),
+		];
+		for (position, color,) in vertices {
+			for c in position {
+				bytes.extend_from_slice(&c.to_be_bytes(),);
+			}
+			bytes.extend_from_slice(&color,);
+		}
+		bytes.push(3,);
+		for index in [0u32, 1, 2,] {
+			bytes.extend_from_slice(&index.to_be_bytes(),);
+		}
+
+		let ply = Ply::parse(&bytes,).unwrap();
+		assert_eq!(ply.indices, vec![[0, 1, 2,]]);
+		assert_eq!(ply.positions[1].e, [1.0, 0.0, 0.0,]);
+		assert_eq!(ply.colors.as_ref().unwrap()[2].e, [0.0, 0.0, 0.2,]);
+
+		// Cutting off the last index leaves the face incomplete.
+		assert!(Ply::parse(&bytes[.. bytes.len() - 1],).is_err());
+	}
+	#[test]
+	fn vertex_colors_follow_barycentric_uvs() {
+		// A strip of quads whose colors grow linearly with x and y, which barycentric weights
+		// reproduce exactly.
+		let quads = 20;
+		let mut file = format!(
+			"ply
+format ascii 1.0
+element vertex {}
+property float x
+property float y
+property float z
+property float red
+property float green
+property float blue
+element face {}
+property list uchar int vertex_indices
+end_header
+",
+			2 * (quads + 1),
+			quads
+		);
+		for x in 0 ..= quads {
+			for y in 0 .. 2 {
+				file += &format!("{} {} 0 {} {} 0\n", x, y, x as f64 / quads as f64, y);
+			}
+		}
+		// Out of order, so that the BVH has to sort them.
+		for q in (0 .. quads).map(|k| 7 * k % quads,) {
+			file += &format!("4 {} {} {} {}\n", 2 * q, 2 * q + 2, 2 * q + 3, 2 * q + 1);
+		}
+
+		let ply = Ply::parse(file.as_bytes(),).unwrap();
+		let texture = ply.vertex_color_texture().unwrap();
+		let mesh = ply
+			.into_mesh(Arc::new(Lambertian::new(Color::new(1.0, 1.0, 1.0,),),),)
+			.with_barycentric_uvs();
+
+		for &(x, y,) in &[(0.1, 0.2,), (7.5, 0.5,), (13.3, 0.9,), (19.9, 0.05,),] {
+			let ray = Ray::new(Point3::new(x, y, 1.0,), Vec3::new(0.0, 0.0, -1.0,), 0.0,);
+			let rec = mesh.hit(&ray, 0.001, f64::INFINITY,).unwrap();
+			let color = texture.value(rec.u, rec.v, &rec.p,);
+			let expected = Color::new(x / quads as f64, y, 0.0,);
+			assert!((color - expected).length() < 1e-9, "{:?} at {} {}", color.e, x, y);
+		}
+	}
+}
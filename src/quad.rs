@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::AABB,
+	hittable::{area_pdf_value, HitRecord, Hittable},
+	material::Material,
+	ray::Ray,
+	sampler::Sampler,
+	vec3::{cross, dot, Point3, unit_vector, Vec3},
+};
+
+/// A parallelogram spanned by the edges `u` and `v` from the corner `q`. Its front side is the
+/// one `u x v` points to, UVs run from 0 to 1 along the two edges.
+pub struct Quad {
+	q:       Point3,
+	u:       Vec3,
+	v:       Vec3,
+	mat_ptr: Arc<dyn Material,>,
+	normal:  Vec3,
+	d:       f64,
+	/// `n / (n . n)` for the unnormalized `n = u x v`, used to find the planar coordinates.
+	w:       Vec3,
+}
+
+impl Quad {
+	pub fn new(q: Point3, u: Vec3, v: Vec3, mat_ptr: Arc<dyn Material,>,) -> Self {
+		let n = cross(&u, &v,);
+		let normal = unit_vector(&n,);
+
+		Quad {
+			q,
+			u,
+			v,
+			mat_ptr,
+			normal,
+			d: dot(&normal, &q,),
+			w: n / dot(&n, &n,),
+		}
+	}
+}
+
+impl Hittable for Quad {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let denom = dot(&self.normal, &r.direction(),);
+		if denom.abs() < 1e-8 {
+			return None;
+		}
+
+		let t = (self.d - dot(&self.normal, &r.origin(),)) / denom;
+		if t < t_min || t > t_max {
+			return None;
+		}
+
+		let p = r.at(t,);
+		let planar = p - self.q;
+		let alpha = dot(&self.w, &cross(&planar, &self.v,),);
+		let beta = dot(&self.w, &cross(&self.u, &planar,),);
+		if !(0.0 ..= 1.0).contains(&alpha,) || !(0.0 ..= 1.0).contains(&beta,) {
+			return None;
+		}
+
+		let mut rec = HitRecord {
+			p,
+			normal: Default::default(),
+			mat_ptr: self.mat_ptr.clone(),
+			t,
+			u: alpha,
+			v: beta,
+			front_face: false,
+		};
+		rec.set_face_normal(r, &self.normal,);
+
+		Some(rec,)
+	}
+
+	/// The box around the four corners, padded along an axis the quad is flat in.
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> {
+		let corners = [self.q + self.u, self.q + self.v, self.q + self.u + self.v,];
+		let mut min = self.q;
+		let mut max = self.q;
+
+		for corner in &corners {
+			for c in 0 .. 3 {
+				min[c] = min[c].min(corner[c],);
+				max[c] = max[c].max(corner[c],);
+			}
+		}
+
+		for c in 0 .. 3 {
+			if max[c] - min[c] < 0.0001 {
+				min[c] -= 0.0001;
+				max[c] += 0.0001;
+			}
+		}
+
+		Some(AABB::new(min, max,),)
+	}
+
+	fn pdf_value(&self, origin: &Point3, direction: &Vec3,) -> f64 {
+		let area = cross(&self.u, &self.v,).length();
+		area_pdf_value(self, area, origin, direction,)
+	}
+
+	fn random(&self, origin: &Point3, sampler: &mut dyn Sampler,) -> Vec3 {
+		let (u0, u1,) = sampler.get_2d();
+		self.q + u0 * self.u + u1 * self.v - *origin
+	}
+}
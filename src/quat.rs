@@ -0,0 +1,175 @@
+use std::ops::Mul;
+
+use crate::{
+	util::degrees_to_radians,
+	vec3::{cross, dot, unit_vector, Vec3},
+};
+
+/// A quaternion `w + v`, where unit quaternions represent rotations.
+#[derive(Clone, Copy,)]
+pub struct Quat {
+	pub w: f64,
+	pub v: Vec3,
+}
+
+impl Default for Quat {
+	fn default() -> Self { Quat::identity() }
+}
+
+impl Quat {
+	pub fn new(w: f64, v: Vec3,) -> Self { Quat { w, v, } }
+
+	pub fn identity() -> Self { Quat::new(1.0, Vec3::default(),) }
+
+	/// A counter-clockwise rotation by `angle` degrees about `axis`, seen looking down the axis.
+	pub fn from_axis_angle(axis: Vec3, angle: f64,) -> Self {
+		let (sin, cos,) = (0.5 * degrees_to_radians(angle,)).sin_cos();
+		Quat::new(cos, sin * unit_vector(&axis,),)
+	}
+
+	/// The axis and angle in degrees of a unit quaternion, the angle lies in `0 ..= 360`. The
+	/// identity has no axis of its own and returns the x axis.
+	pub fn to_axis_angle(self,) -> (Vec3, f64,) {
+		let sin = self.v.length();
+		if sin < 1e-12 {
+			return (Vec3::new(1.0, 0.0, 0.0,), 0.0,);
+		}
+		(self.v / sin, 2.0 * sin.atan2(self.w,).to_degrees(),)
+	}
+
+	pub fn dot(&self, other: &Quat,) -> f64 { self.w * other.w + dot(&self.v, &other.v,) }
+
+	pub fn length(&self,) -> f64 { self.dot(self,).sqrt() }
+
+	pub fn normalize(&self,) -> Self {
+		let length = self.length();
+		Quat::new(self.w / length, self.v / length,)
+	}
+
+	pub fn conjugate(&self,) -> Self { Quat::new(self.w, -self.v,) }
+
+	/// Rotates `v` by this unit quaternion.
+	pub fn rotate(&self, v: &Vec3,) -> Vec3 {
+		let t = 2.0 * cross(&self.v, v,);
+		*v + self.w * t + cross(&self.v, &t,)
+	}
+
+	/// Spherical linear interpolation from `a` at `t = 0` to `b` at `t = 1`, along the shorter
+	/// of the two arcs between them.
+	pub fn slerp(a: &Quat, b: &Quat, t: f64,) -> Self {
+		let mut b = *b;
+		let mut cos = a.dot(&b,);
+		if cos < 0.0 {
+			b = Quat::new(-b.w, -b.v,);
+			cos = -cos;
+		}
+
+		// Nearly parallel quaternions divide by a vanishing sine, a normalized lerp is as good.
+		if cos > 0.9995 {
+			return Quat::new(a.w + t * (b.w - a.w), a.v + t * (b.v - a.v),).normalize();
+		}
+
+		let theta = cos.acos();
+		let sin = theta.sin();
+		let wa = ((1.0 - t) * theta).sin() / sin;
+		let wb = (t * theta).sin() / sin;
+		Quat::new(wa * a.w + wb * b.w, wa * a.v + wb * b.v,)
+	}
+}
+
+/// The Hamilton product, `a * b` rotates by `b` first and then by `a`.
+impl Mul for Quat {
+	type Output = Quat;
+
+	fn mul(self, rhs: Quat,) -> Self::Output {
+		Quat::new(
+			self.w * rhs.w - dot(&self.v, &rhs.v,),
+			self.w * rhs.v + rhs.w * self.v + cross(&self.v, &rhs.v,),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::matrix::Mat3;
+
+	fn assert_close(a: Vec3, b: Vec3,) {
+		assert!((a - b).length() < 1e-9, "{:?} != {:?}", a.e, b.e);
+	}
+
+	#[test]
+	fn rotates_like_its_matrix() {
+		let q = Quat::from_axis_angle(Vec3::new(1.0, -1.0, 2.0,), 71.0,);
+		let matrix = Mat3::from(q,);
+		for v in [Vec3::new(1.0, 0.0, 0.0,), Vec3::new(0.3, -2.0, 5.0,),] {
+			assert_close(q.rotate(&v,), matrix * v,);
+		}
+	}
+
+	#[test]
+	fn product_rotates_by_the_right_factor_first() {
+		let a = Quat::from_axis_angle(Vec3::new(0.0, 0.0, 1.0,), 90.0,);
+		let b = Quat::from_axis_angle(Vec3::new(1.0, 0.0, 0.0,), 90.0,);
+		let v = Vec3::new(0.0, 1.0, 0.0,);
+		assert_close((a * b).rotate(&v,), a.rotate(&b.rotate(&v,),),);
+		assert_close((a * b).rotate(&v,), Vec3::new(0.0, 0.0, 1.0,),);
+	}
+
+	#[test]
+	fn slerp_hits_its_ends_and_halves_the_angle() {
+		let axis = Vec3::new(0.0, 1.0, 0.0,);
+		let a = Quat::from_axis_angle(axis, 10.0,);
+		let b = Quat::from_axis_angle(axis, 130.0,);
+		let v = Vec3::new(1.0, 0.0, 0.0,);
+
+		assert_close(Quat::slerp(&a, &b, 0.0,).rotate(&v,), a.rotate(&v,),);
+		assert_close(Quat::slerp(&a, &b, 1.0,).rotate(&v,), b.rotate(&v,),);
+		assert_close(
+			Quat::slerp(&a, &b, 0.5,).rotate(&v,),
+			Quat::from_axis_angle(axis, 70.0,).rotate(&v,),
+		);
+		assert!((Quat::slerp(&a, &b, 0.3,).length() - 1.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn slerp_takes_the_shorter_arc() {
+		let axis = Vec3::new(0.0, 0.0, 1.0,);
+		let a = Quat::from_axis_angle(axis, 0.0,);
+		let b = Quat::from_axis_angle(axis, 300.0,);
+		let v = Vec3::new(1.0, 0.0, 0.0,);
+
+		// 300 degrees one way is 60 the other, halfway is at -30.
+		assert_close(
+			Quat::slerp(&a, &b, 0.5,).rotate(&v,),
+			Quat::from_axis_angle(axis, -30.0,).rotate(&v,),
+		);
+	}
+
+	#[test]
+	fn slerp_between_nearly_equal_rotations() {
+		let axis = Vec3::new(1.0, 1.0, 1.0,);
+		let a = Quat::from_axis_angle(axis, 45.0,);
+		let b = Quat::from_axis_angle(axis, 45.001,);
+		let q = Quat::slerp(&a, &b, 0.5,);
+		assert!((q.length() - 1.0).abs() < 1e-12);
+		assert!(q.dot(&a,) > 0.999_999);
+	}
+
+	#[test]
+	fn axis_angle_round_trips() {
+		for &(axis, angle,) in &[
+			(Vec3::new(0.0, 0.0, 1.0,), 30.0,),
+			(Vec3::new(1.0, -2.0, 0.5,), 123.0,),
+			(Vec3::new(-3.0, 0.0, 4.0,), 250.0,),
+		] {
+			let (round_axis, round_angle,) = Quat::from_axis_angle(axis, angle,).to_axis_angle();
+			assert_close(round_axis, unit_vector(&axis,),);
+			assert!((round_angle - angle).abs() < 1e-9, "{} != {}", round_angle, angle);
+		}
+
+		let (axis, angle,) = Quat::identity().to_axis_angle();
+		assert_close(axis, Vec3::new(1.0, 0.0, 0.0,),);
+		assert_eq!(angle, 0.0);
+	}
+}
@@ -0,0 +1,421 @@
+use std::{
+	hint::black_box,
+	str::FromStr,
+	sync::atomic::{AtomicUsize, Ordering},
+	time::Instant,
+};
+
+use rayon::prelude::*;
+
+use crate::{
+	bvh::{BVHNode, LinearBVH, MAX_OBJECTS_IN_LEAF},
+	camera::Camera,
+	framebuffer::FrameBuffer,
+	hittable::{HitRecord, Hittable},
+	hittable_list::HittableList,
+	material::{Bounce, ScatterRecord},
+	ray::Ray,
+	sampler::{IndependentSampler, Sampler, SamplerKind},
+	vec3::Color,
+};
+
+pub const TILE_SIZE: usize = 32;
+/// Camera rays along each side of the image that `compare_bvhs` traces.
+const PROBE_SIZE: usize = 64;
+/// How far apart a shadow ray's first hit and its light may be and still be the same surface.
+const SHADOW_EPSILON: f64 = 1e-6;
+
+/// How the light sample and the material sample at a bounce are weighed against each other.
+/// Both favour whichever technique is more likely to pick a direction, the power heuristic more
+/// strongly, which usually leaves less noise.
+#[derive(Clone, Copy,)]
+pub enum Heuristic {
+	Balance,
+	Power,
+}
+
+impl Heuristic {
+	/// The weight of a sample drawn with density `pdf`, when the other technique would have
+	/// drawn it with density `other`.
+	fn weight(self, pdf: f64, other: f64,) -> f64 {
+		match self {
+			Heuristic::Balance => pdf / (pdf + other),
+			Heuristic::Power => pdf * pdf / (pdf * pdf + other * other),
+		}
+	}
+}
+
+impl FromStr for Heuristic {
+	type Err = String;
+
+	fn from_str(s: &str,) -> Result<Self, Self::Err,> {
+		match s {
+			"balance" => Ok(Heuristic::Balance,),
+			"power" => Ok(Heuristic::Power,),
+			_ => Err(format!("unknown heuristic `{}`", s),),
+		}
+	}
+}
+
+/// How many bounces of each kind a path may take. A path that runs out still picks up the light
+/// arriving directly at its last bounce.
+#[derive(Clone, Copy,)]
+pub struct BounceLimits {
+	pub diffuse:      i32,
+	pub glossy:       i32,
+	pub transmission: i32,
+	pub volume:       i32,
+}
+
+impl BounceLimits {
+	fn get(&self, bounce: Bounce,) -> i32 {
+		match bounce {
+			Bounce::Diffuse => self.diffuse,
+			Bounce::Glossy => self.glossy,
+			Bounce::Transmission => self.transmission,
+			Bounce::Volume => self.volume,
+		}
+	}
+}
+
+/// Everything a path needs besides the ray itself: what it can hit, which of it is sampled as a
+/// light, what it sees when it leaves the scene and how many bounces it may take.
+pub struct Tracer<'a,> {
+	pub world:          &'a dyn Hittable,
+	pub lights:         &'a HittableList,
+	pub background:     Color,
+	pub max_depth:      i32,
+	pub limits:         BounceLimits,
+	/// Bounces after which paths are terminated at random, the less light they still carry the
+	/// likelier. Survivors are weighed up so the image stays the same on average.
+	pub roulette_depth: i32,
+	pub heuristic:      Heuristic,
+}
+
+impl Tracer<'_,> {
+	/// The light arriving along `r` from the camera. The path is followed bounce by bounce, with
+	/// `throughput` the fraction of light that still makes it back to the camera. At every bounce
+	/// off a material with a scattering density, the light reaching the hit point is sampled
+	/// twice: once in a direction towards `lights`, and once in the direction drawn from the
+	/// material that the path continues in. Both are weighed with multiple importance sampling,
+	/// `emitted_weight` is the weight of the light emitted where the continued path lands.
+	pub fn trace(&self, r: Ray, sampler: &mut dyn Sampler,) -> Color {
+		let mut ray = r;
+		let mut radiance = Color::default();
+		let mut throughput = Color::new(1.0, 1.0, 1.0,);
+		let mut emitted_weight = 1.0;
+		let mut bounces = 0;
+		let mut counts = [0; 4];
+		let mut last = false;
+
+		loop {
+			let rec = match self.world.hit(&ray, 0.001, f64::INFINITY,) {
+				Some(rec,) => rec,
+				None => return radiance + throughput * self.background,
+			};
+
+			if emitted_weight > 0.0 {
+				let emitted = rec.mat_ptr.emitted(rec.u, rec.v, &rec.p,);
+				radiance += emitted_weight * throughput * emitted;
+			}
+			// A path past its limits was only followed for the light its last bounce hits.
+			if last {
+				return radiance;
+			}
+
+			let (scattered, bounce,) = match rec.mat_ptr.scatter(&ray, &rec, sampler,) {
+				None => return radiance,
+				Some(ScatterRecord::Specular {
+					attenuation,
+					ray: scattered,
+					bounce,
+				},) => {
+					throughput = throughput * attenuation;
+					emitted_weight = 1.0;
+					(scattered, bounce,)
+				},
+				Some(ScatterRecord::Pdf { pdf, bounce, },) => {
+					radiance += throughput * self.direct_light(&ray, &rec, sampler,);
+
+					let scattered = Ray::new(rec.p, pdf.generate(sampler,), ray.time(),);
+					let pdf_value = pdf.value(&scattered.direction(),);
+					if pdf_value <= 0.0 {
+						return radiance;
+					}
+
+					let f = rec.mat_ptr.eval(&ray, &rec, &scattered,);
+					let light_pdf = self.lights.pdf_value(&rec.p, &scattered.direction(),);
+					emitted_weight = self.heuristic.weight(pdf_value, light_pdf,);
+					throughput = throughput * f / pdf_value;
+					(scattered, bounce,)
+				},
+			};
+
+			bounces += 1;
+			counts[bounce as usize] += 1;
+			last = bounces > self.max_depth || counts[bounce as usize] > self.limits.get(bounce,);
+
+			if bounces > self.roulette_depth {
+				let survival = throughput
+					.x()
+					.max(throughput.y(),)
+					.max(throughput.z(),)
+					.min(0.95,);
+				if sampler.get_1d() >= survival {
+					return radiance;
+				}
+				throughput /= survival;
+			}
+
+			ray = scattered;
+		}
+	}
+
+	/// One sample of the light that reaches `rec` straight from `lights` and scatters along `r`,
+	/// weighed against the material sampling the same direction.
+	fn direct_light(&self, r: &Ray, rec: &HitRecord, sampler: &mut dyn Sampler,) -> Color {
+		if self.lights.objects.is_empty() {
+			return Color::default();
+		}
+
+		let shadow = Ray::new(rec.p, self.lights.random(&rec.p, sampler,), r.time(),);
+		let light_pdf = self.lights.pdf_value(&rec.p, &shadow.direction(),);
+		// A light seen exactly edge-on has an infinite density and contributes nothing.
+		if light_pdf <= 0.0 || light_pdf.is_infinite() {
+			return Color::default();
+		}
+
+		let f = rec.mat_ptr.eval(r, rec, &shadow,);
+		if f.near_zero() {
+			return Color::default();
+		}
+
+		// The shadow ray has to reach one of `lights` before anything else. Emitters that are not
+		// among them are only found by the material's own samples, counting them here as well
+		// would add their light twice.
+		let light = match self.lights.hit(&shadow, 0.001, f64::INFINITY,) {
+			Some(light,) => light,
+			None => return Color::default(),
+		};
+		match self.world.hit(&shadow, 0.001, light.t + SHADOW_EPSILON,) {
+			Some(first,) if first.t >= light.t - SHADOW_EPSILON => {
+				let bsdf_pdf = rec.mat_ptr.pdf(r, rec, &shadow,);
+				let weight = self.heuristic.weight(light_pdf, bsdf_pdf,);
+				weight * f * first.mat_ptr.emitted(first.u, first.v, &first.p,) / light_pdf
+			},
+			_ => Color::default(),
+		}
+	}
+}
+
+/// A rectangular block of pixels, `x0 .. x1` by `y0 .. y1`, with `y` counted from the top row.
+#[derive(Clone, Copy,)]
+struct Tile {
+	x0: usize,
+	y0: usize,
+	x1: usize,
+	y1: usize,
+}
+
+fn tiles(image_width: usize, image_height: usize,) -> Vec<Tile,> {
+	let mut tiles = Vec::new();
+
+	for y0 in (0 .. image_height).step_by(TILE_SIZE,) {
+		for x0 in (0 .. image_width).step_by(TILE_SIZE,) {
+			tiles.push(Tile {
+				x0,
+				y0,
+				x1: (x0 + TILE_SIZE).min(image_width,),
+				y1: (y0 + TILE_SIZE).min(image_height,),
+			},);
+		}
+	}
+
+	tiles
+}
+
+fn render_tile(
+	tile: Tile,
+	image_height: usize,
+	sampler: &mut dyn Sampler,
+	pixel_color: &(dyn Fn(usize, usize, &mut dyn Sampler,) -> Color + Sync),
+) -> Vec<Color,> {
+	let mut pixels = Vec::with_capacity((tile.x1 - tile.x0) * (tile.y1 - tile.y0),);
+
+	for row in tile.y0 .. tile.y1 {
+		let j = image_height - 1 - row;
+		for i in tile.x0 .. tile.x1 {
+			pixels.push(pixel_color(i, j, sampler,),);
+		}
+	}
+
+	pixels
+}
+
+/// Renders the image tile by tile on all available cores, every tile with its own sampler of
+/// the `sampler` kind.
+pub fn render(
+	tracer: &Tracer,
+	cam: &Camera,
+	image_width: usize,
+	image_height: usize,
+	samples_per_pixel: i32,
+	sampler: SamplerKind,
+) -> FrameBuffer {
+	let tiles = tiles(image_width, image_height,);
+	let remaining = AtomicUsize::new(tiles.len(),);
+	let now = Instant::now();
+
+	let pixel_color = |i: usize, j: usize, sampler: &mut dyn Sampler| {
+		let mut pixel_color = Color::default();
+		for s in 0 .. samples_per_pixel {
+			sampler.start_pixel_sample(i, j, s as usize,);
+			let (du, dv,) = sampler.get_2d();
+			// A single row or column is still spread over the whole view.
+			let u = (i as f64 + du) / (image_width - 1).max(1,) as f64;
+			let v = (j as f64 + dv) / (image_height - 1).max(1,) as f64;
+			let r = cam.get_ray(u, v, sampler,);
+			pixel_color += tracer.trace(r, sampler,);
+		}
+		pixel_color / samples_per_pixel as f64
+	};
+
+	let rendered: Vec<(Tile, Vec<Color,>,),> = tiles
+		.par_iter()
+		.map(|&tile| {
+			let mut sampler = sampler.create(samples_per_pixel.max(0,) as usize,);
+			let pixels = render_tile(tile, image_height, &mut *sampler, &pixel_color,);
+
+			let left = remaining.fetch_sub(1, Ordering::Relaxed,) - 1;
+			eprint!(
+				"\r{} tiles remaining; {:.1}s;",
+				left,
+				now.elapsed().as_secs_f64()
+			);
+
+			(tile, pixels,)
+		},)
+		.collect();
+
+	let mut image = FrameBuffer::new(image_width, image_height,);
+	for (tile, pixels,) in rendered {
+		let width = tile.x1 - tile.x0;
+		for (row, line,) in pixels.chunks(width,).enumerate() {
+			let start = (tile.y0 + row) * image_width + tile.x0;
+			image.pixels[start .. start + width].copy_from_slice(line,);
+		}
+	}
+
+	image
+}
+
+/// How long the same camera rays took through the pointer tree and the flattened BVH.
+pub struct BvhComparison {
+	pub nodes:  usize,
+	pub tree:   f64,
+	pub linear: f64,
+}
+
+impl BvhComparison {
+	pub fn speedup(&self,) -> f64 { self.tree / self.linear }
+}
+
+/// Builds both kinds of BVH over the bounded objects of `world` and times a grid of camera rays
+/// through each. Returns `None` when there is nothing to build a BVH over.
+pub fn compare_bvhs(world: &HittableList, cam: &Camera,) -> Option<BvhComparison,> {
+	let bounded: Vec<_,> = world
+		.objects
+		.iter()
+		.filter(|object| object.bounding_box(0.0, 1.0,).is_some(),)
+		.cloned()
+		.collect();
+	if bounded.is_empty() {
+		return None;
+	}
+
+	let tree = BVHNode::build(&bounded, 0.0, 1.0, MAX_OBJECTS_IN_LEAF,);
+	let linear = LinearBVH::build(&bounded, 0.0, 1.0, MAX_OBJECTS_IN_LEAF,);
+
+	let rays: Vec<Ray,> = (0 .. PROBE_SIZE * PROBE_SIZE)
+		.map(|k| {
+			let u = ((k % PROBE_SIZE) as f64 + 0.5) / PROBE_SIZE as f64;
+			let v = ((k / PROBE_SIZE) as f64 + 0.5) / PROBE_SIZE as f64;
+			cam.get_ray(u, v, &mut IndependentSampler,)
+		},)
+		.collect();
+	// The fastest of a few runs is the least disturbed by whatever else the machine is doing.
+	let time = |bvh: &dyn Hittable| {
+		(0 .. 3)
+			.map(|_| {
+				let now = Instant::now();
+				for r in &rays {
+					black_box(bvh.hit(r, 0.001, f64::INFINITY,),);
+				}
+				now.elapsed().as_secs_f64()
+			},)
+			.fold(f64::INFINITY, f64::min,)
+	};
+
+	Some(BvhComparison {
+		nodes:  linear.node_count(),
+		tree:   time(&*tree,),
+		linear: time(&linear,),
+	},)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::*;
+	use crate::{
+		aarect::XZRect,
+		material::{DiffuseLight, Lambertian},
+		sphere::Sphere,
+		texture::SolidColor,
+		vec3::{Point3, Vec3},
+	};
+
+	#[test]
+	fn emitters_that_are_not_lights_only_shadow_them() {
+		let glow = Arc::new(DiffuseLight::new(Arc::new(SolidColor::new(4.0, 4.0, 4.0,),),),);
+		let floor = Arc::new(XZRect::new(
+			-10.0,
+			10.0,
+			-10.0,
+			10.0,
+			0.0,
+			Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5,),),),
+		),);
+		let light = Arc::new(Sphere::new(Point3::new(0.0, 10.0, 0.0,), 1.0, glow.clone(),),);
+		// Covers all of `light` as seen from the floor below it, but is not among the lights.
+		let cover = Arc::new(Sphere::new(Point3::new(0.0, 5.0, 0.0,), 2.0, glow,),);
+
+		let mut world = HittableList::default();
+		world.add(floor,);
+		world.add(light.clone(),);
+		world.add(cover,);
+		let lights = HittableList::new(light,);
+		let tracer = Tracer {
+			world:          &world,
+			lights:         &lights,
+			background:     Color::default(),
+			max_depth:      1,
+			limits:         BounceLimits {
+				diffuse:      1,
+				glossy:       1,
+				transmission: 1,
+				volume:       1,
+			},
+			roulette_depth: 1,
+			heuristic:      Heuristic::Balance,
+		};
+
+		let r = Ray::new(Point3::new(0.0, 1.0, 0.0,), Vec3::new(0.0, -1.0, 0.0,), 0.0,);
+		let rec = world.hit(&r, 0.001, f64::INFINITY,).unwrap();
+		let mut sampler = IndependentSampler;
+		for _ in 0 .. 16 {
+			assert!(tracer.direct_light(&r, &rec, &mut sampler,).near_zero());
+		}
+	}
+}
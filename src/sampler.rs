@@ -0,0 +1,407 @@
+use std::str::FromStr;
+
+use crate::util::random_f64;
+
+/// The largest `f64` below one, sample values are clamped to it to stay inside `[0, 1)`.
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+/// Dimensions the Halton sampler has a prime base for, later dimensions are independent.
+const HALTON_DIMENSIONS: usize = 256;
+
+/// A source of values in `[0, 1)` for everything random along a path. Each sample of a pixel is
+/// a point with as many dimensions as its path uses, handed out one or two at a time. Samplers
+/// other than the independent one spread the samples of a pixel more evenly than random numbers
+/// would, which makes the image converge faster.
+pub trait Sampler {
+	/// Starts sample `index` of the pixel at `(x, y)`, the next value comes from its first
+	/// dimension.
+	fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize,);
+
+	fn get_1d(&mut self,) -> f64;
+
+	fn get_2d(&mut self,) -> (f64, f64,);
+}
+
+/// Every value independently random, like `random_f64`.
+pub struct IndependentSampler;
+
+impl Sampler for IndependentSampler {
+	fn start_pixel_sample(&mut self, _x: usize, _y: usize, _index: usize,) {}
+
+	fn get_1d(&mut self,) -> f64 { random_f64() }
+
+	fn get_2d(&mut self,) -> (f64, f64,) { (random_f64(), random_f64(),) }
+}
+
+/// Splits every dimension into as many strata as there are samples per pixel, 2D values into a
+/// grid, and puts each sample in its own stratum with a random offset inside it. Which sample
+/// gets which stratum is shuffled per pixel and dimension, so that dimensions stay uncorrelated.
+pub struct StratifiedSampler {
+	samples_per_pixel: usize,
+	/// Columns of the grid for 2D values, it has `samples_per_pixel / x_strata` rows.
+	x_strata:          usize,
+	pixel:             (usize, usize,),
+	index:             usize,
+	dimension:         usize,
+}
+
+impl StratifiedSampler {
+	/// The grid is as close to square as `samples_per_pixel` allows.
+	pub fn new(samples_per_pixel: usize,) -> Self {
+		let samples_per_pixel = samples_per_pixel.max(1,);
+		let x_strata = (1 ..= samples_per_pixel)
+			.take_while(|x| x * x <= samples_per_pixel,)
+			.filter(|x| samples_per_pixel.is_multiple_of(*x,),)
+			.last()
+			.unwrap_or(1,);
+
+		StratifiedSampler {
+			samples_per_pixel,
+			x_strata,
+			pixel: (0, 0,),
+			index: 0,
+			dimension: 0,
+		}
+	}
+
+	/// The stratum of the current sample in the next dimension.
+	fn stratum(&mut self,) -> usize {
+		let hash = hash(self.pixel, self.dimension,);
+		self.dimension += 1;
+		permutation_element(
+			(self.index % self.samples_per_pixel) as u32,
+			self.samples_per_pixel as u32,
+			hash as u32,
+		) as usize
+	}
+}
+
+impl Sampler for StratifiedSampler {
+	fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize,) {
+		self.pixel = (x, y,);
+		self.index = index;
+		self.dimension = 0;
+	}
+
+	fn get_1d(&mut self,) -> f64 {
+		let stratum = self.stratum();
+		((stratum as f64 + random_f64()) / self.samples_per_pixel as f64).min(ONE_MINUS_EPSILON,)
+	}
+
+	fn get_2d(&mut self,) -> (f64, f64,) {
+		let stratum = self.stratum();
+		let y_strata = self.samples_per_pixel / self.x_strata;
+		let x = (stratum % self.x_strata) as f64 + random_f64();
+		let y = (stratum / self.x_strata) as f64 + random_f64();
+		(
+			(x / self.x_strata as f64).min(ONE_MINUS_EPSILON,),
+			(y / y_strata as f64).min(ONE_MINUS_EPSILON,),
+		)
+	}
+}
+
+/// The Halton sequence, dimension `d` is the radical inverse of the sample index in the `d`th
+/// prime. The digits are Owen scrambled per pixel, which decorrelates pixels and fixes the
+/// patterns that larger bases otherwise show.
+pub struct HaltonSampler {
+	primes:    Vec<u64,>,
+	pixel:     (usize, usize,),
+	index:     u64,
+	dimension: usize,
+}
+
+impl Default for HaltonSampler {
+	fn default() -> Self {
+		let mut primes = Vec::with_capacity(HALTON_DIMENSIONS,);
+		let mut candidate = 2;
+		while primes.len() < HALTON_DIMENSIONS {
+			if primes.iter().all(|p| candidate % p != 0,) {
+				primes.push(candidate,);
+			}
+			candidate += 1;
+		}
+
+		HaltonSampler {
+			primes,
+			pixel: (0, 0,),
+			index: 0,
+			dimension: 0,
+		}
+	}
+}
+
+impl Sampler for HaltonSampler {
+	fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize,) {
+		self.pixel = (x, y,);
+		self.index = index as u64;
+		self.dimension = 0;
+	}
+
+	fn get_1d(&mut self,) -> f64 {
+		let dimension = self.dimension;
+		self.dimension += 1;
+		match self.primes.get(dimension,) {
+			Some(&base,) =>
+				owen_scrambled_radical_inverse(base, self.index, hash(self.pixel, dimension,),),
+			None => random_f64(),
+		}
+	}
+
+	fn get_2d(&mut self,) -> (f64, f64,) { (self.get_1d(), self.get_1d(),) }
+}
+
+/// The first two dimensions of the Sobol sequence, Owen scrambled per pixel. Every value handed
+/// out takes the same two dimensions, but with its own shuffle of the sample order, which keeps
+/// later dimensions uncorrelated without needing Sobol dimensions for all of them.
+#[derive(Default,)]
+pub struct SobolSampler {
+	pixel:     (usize, usize,),
+	index:     u32,
+	dimension: usize,
+}
+
+impl SobolSampler {
+	/// The scrambling seeds for the next dimension, and the shuffled sample index.
+	fn next(&mut self,) -> (u64, u32,) {
+		let hash = hash(self.pixel, self.dimension,);
+		self.dimension += 1;
+		(hash, nested_uniform_scramble(self.index, hash as u32,),)
+	}
+}
+
+impl Sampler for SobolSampler {
+	fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize,) {
+		self.pixel = (x, y,);
+		self.index = index as u32;
+		self.dimension = 0;
+	}
+
+	fn get_1d(&mut self,) -> f64 {
+		let (hash, index,) = self.next();
+		to_unit(nested_uniform_scramble(
+			index.reverse_bits(),
+			(hash >> 32) as u32,
+		),)
+	}
+
+	fn get_2d(&mut self,) -> (f64, f64,) {
+		let (hash, index,) = self.next();
+		(
+			to_unit(nested_uniform_scramble(
+				index.reverse_bits(),
+				(hash >> 32) as u32,
+			),),
+			to_unit(nested_uniform_scramble(
+				sobol_second_dimension(index,),
+				mix_bits(hash,) as u32,
+			),),
+		)
+	}
+}
+
+/// The samplers that can be picked per render.
+#[derive(Clone, Copy,)]
+pub enum SamplerKind {
+	Independent,
+	Stratified,
+	Halton,
+	Sobol,
+}
+
+impl SamplerKind {
+	/// A new sampler of this kind, for pixels that take `samples_per_pixel` samples.
+	pub fn create(self, samples_per_pixel: usize,) -> Box<dyn Sampler,> {
+		match self {
+			SamplerKind::Independent => Box::new(IndependentSampler,),
+			SamplerKind::Stratified => Box::new(StratifiedSampler::new(samples_per_pixel,),),
+			SamplerKind::Halton => Box::new(HaltonSampler::default(),),
+			SamplerKind::Sobol => Box::new(SobolSampler::default(),),
+		}
+	}
+}
+
+impl FromStr for SamplerKind {
+	type Err = String;
+
+	fn from_str(s: &str,) -> Result<Self, Self::Err,> {
+		match s {
+			"independent" => Ok(SamplerKind::Independent,),
+			"stratified" => Ok(SamplerKind::Stratified,),
+			"halton" => Ok(SamplerKind::Halton,),
+			"sobol" => Ok(SamplerKind::Sobol,),
+			_ => Err(format!("unknown sampler `{}`", s),),
+		}
+	}
+}
+
+/// Scrambles the bits of `v`, so that nearby inputs give unrelated outputs.
+fn mix_bits(mut v: u64,) -> u64 {
+	v ^= v >> 31;
+	v = v.wrapping_mul(0x7fb5_d329_728e_a185,);
+	v ^= v >> 27;
+	v = v.wrapping_mul(0x81da_def4_bc2d_d44d,);
+	v ^= v >> 33;
+	v
+}
+
+/// The seed for everything that differs between pixels and dimensions.
+fn hash((x, y,): (usize, usize,), dimension: usize,) -> u64 {
+	let mut hash = 0x9e37_79b9_7f4a_7c15;
+	for v in [x, y, dimension,] {
+		hash = mix_bits(hash ^ v as u64,);
+	}
+	hash
+}
+
+/// Element `i` of a random permutation of `0 .. l`, chosen by `p`, without building the whole
+/// permutation. This is Kensler's hash from "Correlated Multi-Jittered Sampling".
+fn permutation_element(mut i: u32, l: u32, p: u32,) -> u32 {
+	let mut w = l - 1;
+	w |= w >> 1;
+	w |= w >> 2;
+	w |= w >> 4;
+	w |= w >> 8;
+	w |= w >> 16;
+
+	// Values outside `0 .. l` are hashed again until they fall inside.
+	loop {
+		i ^= p;
+		i = i.wrapping_mul(0xe170_893d,);
+		i ^= p >> 16;
+		i ^= (i & w) >> 4;
+		i ^= p >> 8;
+		i = i.wrapping_mul(0x0929_eb3f,);
+		i ^= p >> 23;
+		i ^= (i & w) >> 1;
+		i = i.wrapping_mul(1 | p >> 27,);
+		i = i.wrapping_mul(0x6935_fa69,);
+		i ^= (i & w) >> 11;
+		i = i.wrapping_mul(0x74dc_b303,);
+		i ^= (i & w) >> 2;
+		i = i.wrapping_mul(0x9e50_1cc3,);
+		i ^= (i & w) >> 2;
+		i = i.wrapping_mul(0xc860_a3df,);
+		i &= w;
+		i ^= i >> 5;
+		if i < l {
+			return ((i as u64 + p as u64) % l as u64) as u32;
+		}
+	}
+}
+
+/// The radical inverse of `a` in `base`, with every digit permuted by a hash of the digits
+/// before it. Digits past the last one of `a` are zeros that get permuted as well.
+fn owen_scrambled_radical_inverse(base: u64, mut a: u64, hash: u64,) -> f64 {
+	let inv_base = 1.0 / base as f64;
+	let mut inv_base_m = 1.0;
+	let mut reversed_digits = 0;
+
+	// Until the next digit is too small to change the result, or no longer fits.
+	while 1.0 - (base - 1) as f64 * inv_base_m < 1.0 && reversed_digits < u64::MAX / base {
+		let next = a / base;
+		let digit_hash = mix_bits(hash ^ reversed_digits,);
+		let digit = permutation_element((a - next * base) as u32, base as u32, digit_hash as u32,);
+		reversed_digits = reversed_digits * base + digit as u64;
+		inv_base_m *= inv_base;
+		a = next;
+	}
+
+	(inv_base_m * reversed_digits as f64).min(ONE_MINUS_EPSILON,)
+}
+
+/// An Owen scramble of the bits of `x` read as a binary fraction, Burley's hash based version
+/// from "Practical Hash-based Owen Scrambling".
+fn nested_uniform_scramble(x: u32, seed: u32,) -> u32 {
+	let mut x = x.reverse_bits();
+	x ^= x.wrapping_mul(0x3d20_adea,);
+	x = x.wrapping_add(seed,);
+	x = x.wrapping_mul((seed >> 16) | 1,);
+	x ^= x.wrapping_mul(0x0552_6c56,);
+	x ^= x.wrapping_mul(0x53a2_2864,);
+	x.reverse_bits()
+}
+
+/// The second dimension of the Sobol sequence, whose direction numbers follow from the
+/// primitive polynomial `x + 1`. The first dimension is the bit reversal of the index.
+fn sobol_second_dimension(mut index: u32,) -> u32 {
+	let mut direction = 1 << 31;
+	let mut x = 0;
+	while index != 0 {
+		if index & 1 != 0 {
+			x ^= direction;
+		}
+		index >>= 1;
+		direction ^= direction >> 1;
+	}
+	x
+}
+
+fn to_unit(x: u32,) -> f64 { x as f64 / 4_294_967_296.0 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn permutation_element_is_a_bijection() {
+		for l in 1 .. 70 {
+			for p in [0, 1, 0x1234_5678, 0xdead_beef, u32::MAX,] {
+				let mut seen = vec![false; l as usize];
+				for i in 0 .. l {
+					let element = permutation_element(i, l, p,);
+					assert!(element < l, "{} out of range for l = {}", element, l);
+					assert!(!seen[element as usize], "{} repeated for l = {}, p = {}", element, l, p);
+					seen[element as usize] = true;
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn stratified_puts_one_sample_in_each_stratum() {
+		for &samples_per_pixel in &[1, 7, 12, 16,] {
+			let mut sampler = StratifiedSampler::new(samples_per_pixel,);
+			let x_strata = sampler.x_strata;
+			let y_strata = samples_per_pixel / x_strata;
+			for &(x, y,) in &[(0, 0,), (3, 1,), (640, 479,),] {
+				let mut first = vec![0; samples_per_pixel];
+				let mut grid = vec![0; samples_per_pixel];
+				let mut third = vec![0; samples_per_pixel];
+				for index in 0 .. samples_per_pixel {
+					sampler.start_pixel_sample(x, y, index,);
+					first[(sampler.get_1d() * samples_per_pixel as f64) as usize] += 1;
+					let (u, v,) = sampler.get_2d();
+					let column = (u * x_strata as f64) as usize;
+					let row = (v * y_strata as f64) as usize;
+					grid[row * x_strata + column] += 1;
+					third[(sampler.get_1d() * samples_per_pixel as f64) as usize] += 1;
+				}
+				for strata in &[first, grid, third,] {
+					assert!(strata.iter().all(|&count| count == 1), "{:?}", strata);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn samples_lie_in_the_unit_interval() {
+		let kinds = [
+			SamplerKind::Independent,
+			SamplerKind::Stratified,
+			SamplerKind::Halton,
+			SamplerKind::Sobol,
+		];
+		for &kind in &kinds {
+			let mut sampler = kind.create(16,);
+			for index in 0 .. 64 {
+				sampler.start_pixel_sample(5, 9, index,);
+				for _ in 0 .. 300 {
+					let value = sampler.get_1d();
+					let (u, v,) = sampler.get_2d();
+					for value in [value, u, v,] {
+						assert!((0.0 .. 1.0).contains(&value), "{}", value);
+					}
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,976 @@
+use std::{
+	collections::{HashMap, HashSet},
+	fmt::{Display, Formatter},
+	fs::read_to_string,
+	io,
+	path::Path,
+	sync::Arc,
+};
+
+use serde::Deserialize;
+
+use crate::{
+	_box::Box,
+	aabb::AABB,
+	aarect::{XYRect, XZRect, YZRect},
+	animatedtransform::{AnimatedTransform, Keyframe},
+	cone::Cone,
+	constantmedium::ConstantMedium,
+	csg::Csg,
+	cylinder::Cylinder,
+	disk::Disk,
+	gltf_import,
+	hittable::{Hittable, Transform},
+	hittable_list::HittableList,
+	material::{Dielectric, DiffuseLight, Isotrophic, Lambertian, Material, Metal},
+	matrix::Mat4,
+	mesh::TriangleMesh,
+	movingsphere::MovingSphere,
+	obj::{self, ObjError},
+	plane::Plane,
+	ply::{Ply, PlyError},
+	quad::Quad,
+	quat::Quat,
+	scenes::Scene,
+	sdf::{
+		self, Mandelbulb, Repeat, Sdf, SdfBox, SdfHittable, SdfSphere, SdfTorus, SmoothUnion, Twist,
+	},
+	sphere::Sphere,
+	stl::{self, StlError},
+	texture::{CheckerTexture, ImageTexture, NoiseTexture, SolidColor, Texture},
+	torus::Torus,
+	triangle::Triangle,
+	vec3::{Color, Vec3},
+};
+
+/// Everything that can go wrong while loading a scene file.
+#[derive(Debug,)]
+pub enum SceneError {
+	Io(io::Error,),
+	Parse(serde_json::Error,),
+	UnknownTexture(String,),
+	UnknownMaterial(String,),
+	NoVertexColors(String,),
+	InvalidMesh,
+	SingularTransform,
+	NoKeyframes,
+	Obj(ObjError,),
+	Gltf(gltf::Error,),
+	Ply(PlyError,),
+	Stl(StlError,),
+}
+
+impl Display for SceneError {
+	fn fmt(&self, f: &mut Formatter<'_,>,) -> std::fmt::Result {
+		match self {
+			SceneError::Io(e,) => write!(f, "{}", e),
+			SceneError::Parse(e,) => write!(f, "{}", e),
+			SceneError::UnknownTexture(name,) => write!(f, "unknown texture `{}`", name),
+			SceneError::UnknownMaterial(name,) => write!(f, "unknown material `{}`", name),
+			SceneError::NoVertexColors(name,) => write!(
+				f,
+				"texture `{}` is made of vertex colors, only PLY objects with colors have them",
+				name
+			),
+			SceneError::Obj(e,) => write!(f, "{}", e),
+			SceneError::Gltf(e,) => write!(f, "{}", e),
+			SceneError::Ply(e,) => write!(f, "{}", e),
+			SceneError::Stl(e,) => write!(f, "{}", e),
+			SceneError::SingularTransform => write!(f, "transform matrix is not invertible"),
+			SceneError::NoKeyframes => write!(f, "an animated object needs at least one keyframe"),
+			SceneError::InvalidMesh => write!(
+				f,
+				"mesh normals and uvs must match its positions and indices must be in range"
+			),
+		}
+	}
+}
+
+impl From<io::Error,> for SceneError {
+	fn from(e: io::Error,) -> Self { SceneError::Io(e,) }
+}
+
+impl From<ObjError,> for SceneError {
+	fn from(e: ObjError,) -> Self { SceneError::Obj(e,) }
+}
+
+impl From<gltf::Error,> for SceneError {
+	fn from(e: gltf::Error,) -> Self { SceneError::Gltf(e,) }
+}
+
+impl From<PlyError,> for SceneError {
+	fn from(e: PlyError,) -> Self { SceneError::Ply(e,) }
+}
+
+impl From<StlError,> for SceneError {
+	fn from(e: StlError,) -> Self { SceneError::Stl(e,) }
+}
+
+impl From<serde_json::Error,> for SceneError {
+	fn from(e: serde_json::Error,) -> Self { SceneError::Parse(e,) }
+}
+
+#[derive(Deserialize,)]
+#[serde(deny_unknown_fields)]
+struct SceneDesc {
+	#[serde(default)]
+	camera:            CameraDesc,
+	background:        Option<[f64; 3],>,
+	aspect_ratio:      Option<f64,>,
+	image_width:       Option<usize,>,
+	samples_per_pixel: Option<i32,>,
+	#[serde(default)]
+	textures:          HashMap<String, TextureDesc,>,
+	#[serde(default)]
+	materials:         HashMap<String, MaterialDesc,>,
+	objects:           Vec<ObjectDesc,>,
+}
+
+#[derive(Deserialize, Default,)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+	lookfrom:   Option<[f64; 3],>,
+	lookat:     Option<[f64; 3],>,
+	vup:        Option<[f64; 3],>,
+	vfov:       Option<f64,>,
+	aperture:   Option<f64,>,
+	focus_dist: Option<f64,>,
+}
+
+/// Either a constant color or the name of a texture declared under `textures`.
+#[derive(Deserialize,)]
+#[serde(untagged)]
+enum ColorOrTexture {
+	Color([f64; 3],),
+	Texture(String,),
+}
+
+#[derive(Deserialize,)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum TextureDesc {
+	Solid { color: [f64; 3], },
+	Checker { even: [f64; 3], odd: [f64; 3], },
+	Noise { scale: f64, },
+	Image { path: String, },
+	/// The vertex colors of the PLY object the material is used on.
+	VertexColors,
+}
+
+#[derive(Deserialize,)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+	Lambertian { albedo: ColorOrTexture, },
+	Metal { albedo: ColorOrTexture, fuzz: f64, },
+	Dielectric { ir: f64, },
+	DiffuseLight { emit: ColorOrTexture, },
+	Isotropic { albedo: ColorOrTexture, },
+}
+
+impl MaterialDesc {
+	/// The color or texture the material takes its color from, if it has one.
+	fn color(&self,) -> Option<&ColorOrTexture,> {
+		match self {
+			MaterialDesc::Lambertian { albedo, }
+			| MaterialDesc::Metal { albedo, .. }
+			| MaterialDesc::Isotropic { albedo, } => Some(albedo,),
+			MaterialDesc::DiffuseLight { emit, } => Some(emit,),
+			MaterialDesc::Dielectric { .. } => None,
+		}
+	}
+}
+
+/// Either the name of a material declared under `materials` or an inline definition.
+#[derive(Deserialize,)]
+#[serde(untagged)]
+enum MaterialRef {
+	Named(String,),
+	Inline(MaterialDesc,),
+}
+
+#[derive(Deserialize,)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+	Sphere {
+		center:   [f64; 3],
+		radius:   f64,
+		material: MaterialRef,
+	},
+	MovingSphere {
+		center0:  [f64; 3],
+		center1:  [f64; 3],
+		time0:    f64,
+		time1:    f64,
+		radius:   f64,
+		material: MaterialRef,
+	},
+	XyRect {
+		x0:       f64,
+		x1:       f64,
+		y0:       f64,
+		y1:       f64,
+		k:        f64,
+		material: MaterialRef,
+	},
+	XzRect {
+		x0:       f64,
+		x1:       f64,
+		z0:       f64,
+		z1:       f64,
+		k:        f64,
+		material: MaterialRef,
+	},
+	YzRect {
+		y0:       f64,
+		y1:       f64,
+		z0:       f64,
+		z1:       f64,
+		k:        f64,
+		material: MaterialRef,
+	},
+	Box {
+		p0:       [f64; 3],
+		p1:       [f64; 3],
+		material: MaterialRef,
+	},
+	/// A parallelepiped spanned by three `edges` from `corner`.
+	OrientedBox {
+		corner:   [f64; 3],
+		edges:    [[f64; 3]; 3],
+		material: MaterialRef,
+	},
+	/// A parallelogram spanned by the edges `u` and `v` from the corner `q`.
+	Quad {
+		q:        [f64; 3],
+		u:        [f64; 3],
+		v:        [f64; 3],
+		material: MaterialRef,
+	},
+	Disk {
+		center:   [f64; 3],
+		normal:   [f64; 3],
+		radius:   f64,
+		material: MaterialRef,
+	},
+	/// A cylinder from `base` along `axis`, closed at both ends unless `capped` is false.
+	Cylinder {
+		base:     [f64; 3],
+		axis:     [f64; 3],
+		radius:   f64,
+		#[serde(default = "yes")]
+		capped:   bool,
+		material: MaterialRef,
+	},
+	/// A cone with its base at `base` and its apex at `base + axis`.
+	Cone {
+		base:     [f64; 3],
+		axis:     [f64; 3],
+		radius:   f64,
+		#[serde(default = "yes")]
+		capped:   bool,
+		material: MaterialRef,
+	},
+	Torus {
+		center:       [f64; 3],
+		axis:         [f64; 3],
+		major_radius: f64,
+		minor_radius: f64,
+		material:     MaterialRef,
+	},
+	/// An infinite plane, which stays outside the BVH.
+	Plane {
+		point:    [f64; 3],
+		normal:   [f64; 3],
+		material: MaterialRef,
+	},
+	Triangle {
+		vertices: [[f64; 3]; 3],
+		normals:  Option<[[f64; 3]; 3],>,
+		uvs:      Option<[[f64; 2]; 3],>,
+		material: MaterialRef,
+	},
+	Mesh {
+		positions: Vec<[f64; 3],>,
+		normals:   Option<Vec<[f64; 3],>,>,
+		uvs:       Option<Vec<[f64; 2],>,>,
+		indices:   Vec<[u32; 3],>,
+		material:  MaterialRef,
+	},
+	/// A Wavefront OBJ file, `material` is used for faces without an MTL material.
+	Obj {
+		path:     String,
+		material: Option<MaterialRef,>,
+	},
+	/// The meshes of a glTF scene with their own materials, its camera is ignored.
+	Gltf { path: String, },
+	/// A PLY file, without a `material` its vertex colors are used as a diffuse albedo. Textures
+	/// of type `vertex_colors` in its material stand for the vertex colors of this file.
+	Ply {
+		path:     String,
+		material: Option<MaterialRef,>,
+	},
+	Stl {
+		path:     String,
+		material: MaterialRef,
+	},
+	Translate {
+		offset: [f64; 3],
+		object: std::boxed::Box<ObjectDesc,>,
+	},
+	RotateY {
+		angle:  f64,
+		object: std::boxed::Box<ObjectDesc,>,
+	},
+	/// An object placed by a list of transformations, applied in order.
+	Transform {
+		transforms: Vec<TransformDesc,>,
+		object:     std::boxed::Box<ObjectDesc,>,
+	},
+	/// An object moving through `keyframes` over the shutter interval.
+	Animated {
+		keyframes: Vec<KeyframeDesc,>,
+		object:    std::boxed::Box<ObjectDesc,>,
+	},
+	ConstantMedium {
+		density:  f64,
+		albedo:   ColorOrTexture,
+		boundary: std::boxed::Box<ObjectDesc,>,
+	},
+	/// The surface of a distance function, traced inside the box from `min` to `max`.
+	Sdf {
+		shape:    SdfDesc,
+		min:      [f64; 3],
+		max:      [f64; 3],
+		material: MaterialRef,
+	},
+	/// Everything inside `a` or `b`, both have to be closed solids.
+	Union {
+		a: std::boxed::Box<ObjectDesc,>,
+		b: std::boxed::Box<ObjectDesc,>,
+	},
+	/// Everything inside both `a` and `b`.
+	Intersection {
+		a: std::boxed::Box<ObjectDesc,>,
+		b: std::boxed::Box<ObjectDesc,>,
+	},
+	/// Everything inside `a` with `b` carved out of it.
+	Difference {
+		a: std::boxed::Box<ObjectDesc,>,
+		b: std::boxed::Box<ObjectDesc,>,
+	},
+}
+
+#[derive(Deserialize,)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum TransformDesc {
+	Translate([f64; 3],),
+	Rotate {
+		axis:  [f64; 3],
+		angle: f64,
+	},
+	Scale([f64; 3],),
+	LookAt {
+		from: [f64; 3],
+		to:   [f64; 3],
+		vup:  [f64; 3],
+	},
+	/// Rows of an affine matrix.
+	Matrix([[f64; 4]; 4],),
+}
+
+impl TransformDesc {
+	fn matrix(&self,) -> Mat4 {
+		match self {
+			TransformDesc::Translate(offset,) => Mat4::translation(vec3(*offset,),),
+			TransformDesc::Rotate { axis, angle, } => Mat4::rotation(vec3(*axis,), *angle,),
+			TransformDesc::Scale(factors,) => Mat4::scaling(vec3(*factors,),),
+			TransformDesc::LookAt { from, to, vup, } =>
+				Mat4::look_at(vec3(*from,), vec3(*to,), vec3(*vup,),),
+			TransformDesc::Matrix(m,) => Mat4::new(*m,),
+		}
+	}
+}
+
+#[derive(Deserialize,)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum SdfDesc {
+	Sphere {
+		radius: f64,
+	},
+	Box {
+		half_extents: [f64; 3],
+	},
+	Torus {
+		major_radius: f64,
+		minor_radius: f64,
+	},
+	Mandelbulb {
+		#[serde(default = "mandelbulb_power")]
+		power:      f64,
+		#[serde(default = "mandelbulb_iterations")]
+		iterations: usize,
+	},
+	Translate {
+		offset: [f64; 3],
+		shape:  std::boxed::Box<SdfDesc,>,
+	},
+	SmoothUnion {
+		a: std::boxed::Box<SdfDesc,>,
+		b: std::boxed::Box<SdfDesc,>,
+		k: f64,
+	},
+	Twist {
+		rate:  f64,
+		shape: std::boxed::Box<SdfDesc,>,
+	},
+	/// Copies of `shape` every `period` along the axes, a period of 0 leaves that axis alone.
+	Repeat {
+		period: [f64; 3],
+		shape:  std::boxed::Box<SdfDesc,>,
+	},
+}
+
+impl SdfDesc {
+	fn sdf(&self,) -> Arc<dyn Sdf,> {
+		match self {
+			SdfDesc::Sphere { radius, } => Arc::new(SdfSphere { radius: *radius, },),
+			SdfDesc::Box { half_extents, } => Arc::new(SdfBox {
+				half_extents: vec3(*half_extents,),
+			},),
+			SdfDesc::Torus {
+				major_radius,
+				minor_radius,
+			} => Arc::new(SdfTorus {
+				major_radius: *major_radius,
+				minor_radius: *minor_radius,
+			},),
+			SdfDesc::Mandelbulb { power, iterations, } => Arc::new(Mandelbulb {
+				power:      *power,
+				iterations: *iterations,
+			},),
+			SdfDesc::Translate { offset, shape, } => Arc::new(sdf::Translate {
+				sdf:    shape.sdf(),
+				offset: vec3(*offset,),
+			},),
+			SdfDesc::SmoothUnion { a, b, k, } => Arc::new(SmoothUnion {
+				a: a.sdf(),
+				b: b.sdf(),
+				k: *k,
+			},),
+			SdfDesc::Twist { rate, shape, } => Arc::new(Twist {
+				sdf:  shape.sdf(),
+				rate: *rate,
+			},),
+			SdfDesc::Repeat { period, shape, } => Arc::new(Repeat {
+				sdf:    shape.sdf(),
+				period: vec3(*period,),
+			},),
+		}
+	}
+}
+
+impl ObjectDesc {
+	/// Whether this is a sphere, rectangle or quad made of a diffuse light, which are the emitters
+	/// the renderer can sample directly.
+	fn is_area_light(&self, materials: &HashMap<String, MaterialDesc,>,) -> bool {
+		let material = match self {
+			ObjectDesc::Sphere { material, .. }
+			| ObjectDesc::XyRect { material, .. }
+			| ObjectDesc::XzRect { material, .. }
+			| ObjectDesc::YzRect { material, .. }
+			| ObjectDesc::Quad { material, .. } => material,
+			_ => return false,
+		};
+
+		let desc = match material {
+			MaterialRef::Named(name,) => materials.get(name,),
+			MaterialRef::Inline(desc,) => Some(desc,),
+		};
+		matches!(desc, Some(MaterialDesc::DiffuseLight { .. }))
+	}
+}
+
+fn mandelbulb_power() -> f64 { 8.0 }
+
+fn mandelbulb_iterations() -> usize { 12 }
+
+#[derive(Deserialize,)]
+#[serde(deny_unknown_fields)]
+struct KeyframeDesc {
+	time:      f64,
+	#[serde(default)]
+	translate: [f64; 3],
+	rotate:    Option<RotationDesc,>,
+}
+
+#[derive(Deserialize,)]
+#[serde(deny_unknown_fields)]
+struct RotationDesc {
+	axis:  [f64; 3],
+	angle: f64,
+}
+
+#[inline]
+fn vec3(e: [f64; 3],) -> Vec3 { Vec3 { e, } }
+
+fn yes() -> bool { true }
+
+/// Builds the textures and materials of a scene file once, so objects can share them by name.
+/// Materials made of vertex colors are the exception, they are built for every object instead.
+struct Builder<'a,> {
+	dir:            &'a Path,
+	textures:       HashMap<String, Arc<dyn Texture,>,>,
+	/// The names of the textures of type `vertex_colors`.
+	vertex_colors:  HashSet<String,>,
+	materials:      HashMap<String, Arc<dyn Material,>,>,
+	material_descs: &'a HashMap<String, MaterialDesc,>,
+}
+
+impl Builder<'_,> {
+	/// Resolves `desc`, with `vertex_colors` standing in for textures of type `vertex_colors`.
+	fn texture(
+		&self,
+		desc: &ColorOrTexture,
+		vertex_colors: Option<&Arc<dyn Texture,>,>,
+	) -> Result<Arc<dyn Texture,>, SceneError,> {
+		match desc {
+			ColorOrTexture::Color(color,) => Ok(Arc::new(SolidColor::from(vec3(*color,),),),),
+			ColorOrTexture::Texture(name,) if self.vertex_colors.contains(name,) => vertex_colors
+				.cloned()
+				.ok_or_else(|| SceneError::NoVertexColors(name.clone(),),),
+			ColorOrTexture::Texture(name,) => self
+				.textures
+				.get(name,)
+				.cloned()
+				.ok_or_else(|| SceneError::UnknownTexture(name.clone(),),),
+		}
+	}
+
+	fn material(
+		&self,
+		desc: &MaterialDesc,
+		vertex_colors: Option<&Arc<dyn Texture,>,>,
+	) -> Result<Arc<dyn Material,>, SceneError,> {
+		Ok(match desc {
+			MaterialDesc::Lambertian { albedo, } => Arc::new(Lambertian {
+				albedo: self.texture(albedo, vertex_colors,)?,
+			},),
+			MaterialDesc::Metal { albedo, fuzz, } => Arc::new(Metal {
+				albedo: self.texture(albedo, vertex_colors,)?,
+				fuzz:   *fuzz,
+			},),
+			MaterialDesc::Dielectric { ir, } => Arc::new(Dielectric::new(*ir,),),
+			MaterialDesc::DiffuseLight { emit, } =>
+				Arc::new(DiffuseLight::new(self.texture(emit, vertex_colors,)?,),),
+			MaterialDesc::Isotropic { albedo, } =>
+				Arc::new(Isotrophic::new(self.texture(albedo, vertex_colors,)?,),),
+		},)
+	}
+
+	fn uses_vertex_colors(&self, desc: &MaterialDesc,) -> bool {
+		matches!(
+			desc.color(),
+			Some(ColorOrTexture::Texture(name,)) if self.vertex_colors.contains(name,)
+		)
+	}
+
+	fn material_desc<'b,>(
+		&'b self,
+		mat: &'b MaterialRef,
+	) -> Result<&'b MaterialDesc, SceneError,> {
+		match mat {
+			MaterialRef::Named(name,) => self
+				.material_descs
+				.get(name,)
+				.ok_or_else(|| SceneError::UnknownMaterial(name.clone(),),),
+			MaterialRef::Inline(desc,) => Ok(desc,),
+		}
+	}
+
+	fn material_ref(&self, mat: &MaterialRef,) -> Result<Arc<dyn Material,>, SceneError,> {
+		self.colored_material_ref(mat, None,)
+	}
+
+	/// Like `material_ref`, for an object whose vertex colors are `vertex_colors`.
+	fn colored_material_ref(
+		&self,
+		mat: &MaterialRef,
+		vertex_colors: Option<&Arc<dyn Texture,>,>,
+	) -> Result<Arc<dyn Material,>, SceneError,> {
+		if let MaterialRef::Named(name,) = mat {
+			if let Some(material,) = self.materials.get(name,) {
+				return Ok(material.clone(),);
+			}
+		}
+		self.material(self.material_desc(mat,)?, vertex_colors,)
+	}
+
+	fn object(&self, desc: &ObjectDesc,) -> Result<Arc<dyn Hittable,>, SceneError,> {
+		Ok(match desc {
+			ObjectDesc::Sphere {
+				center,
+				radius,
+				material,
+			} => Arc::new(Sphere::new(
+				vec3(*center,),
+				*radius,
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::MovingSphere {
+				center0,
+				center1,
+				time0,
+				time1,
+				radius,
+				material,
+			} => Arc::new(MovingSphere::new(
+				vec3(*center0,),
+				vec3(*center1,),
+				*time0,
+				*time1,
+				*radius,
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::XyRect {
+				x0,
+				x1,
+				y0,
+				y1,
+				k,
+				material,
+			} => Arc::new(XYRect::new(
+				*x0,
+				*x1,
+				*y0,
+				*y1,
+				*k,
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::XzRect {
+				x0,
+				x1,
+				z0,
+				z1,
+				k,
+				material,
+			} => Arc::new(XZRect::new(
+				*x0,
+				*x1,
+				*z0,
+				*z1,
+				*k,
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::YzRect {
+				y0,
+				y1,
+				z0,
+				z1,
+				k,
+				material,
+			} => Arc::new(YZRect::new(
+				*y0,
+				*y1,
+				*z0,
+				*z1,
+				*k,
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Box { p0, p1, material, } => Arc::new(Box::new(
+				&vec3(*p0,),
+				&vec3(*p1,),
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::OrientedBox {
+				corner,
+				edges: [a, b, c,],
+				material,
+			} => Arc::new(Box::oriented(
+				vec3(*corner,),
+				vec3(*a,),
+				vec3(*b,),
+				vec3(*c,),
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Quad { q, u, v, material, } => Arc::new(Quad::new(
+				vec3(*q,),
+				vec3(*u,),
+				vec3(*v,),
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Disk {
+				center,
+				normal,
+				radius,
+				material,
+			} => Arc::new(Disk::new(
+				vec3(*center,),
+				vec3(*normal,),
+				*radius,
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Cylinder {
+				base,
+				axis,
+				radius,
+				capped,
+				material,
+			} => Arc::new(Cylinder::new(
+				vec3(*base,),
+				vec3(*axis,),
+				*radius,
+				*capped,
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Cone {
+				base,
+				axis,
+				radius,
+				capped,
+				material,
+			} => Arc::new(Cone::new(
+				vec3(*base,),
+				vec3(*axis,),
+				*radius,
+				*capped,
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Torus {
+				center,
+				axis,
+				major_radius,
+				minor_radius,
+				material,
+			} => Arc::new(Torus::new(
+				vec3(*center,),
+				vec3(*axis,),
+				*major_radius,
+				*minor_radius,
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Plane {
+				point,
+				normal,
+				material,
+			} => Arc::new(Plane::new(
+				vec3(*point,),
+				vec3(*normal,),
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Triangle {
+				vertices,
+				normals,
+				uvs,
+				material,
+			} => Arc::new(Triangle::new(
+				[vec3(vertices[0],), vec3(vertices[1],), vec3(vertices[2],),],
+				normals.map(|n| [vec3(n[0],), vec3(n[1],), vec3(n[2],),],),
+				uvs.map(|uv| {
+					[
+						(uv[0][0], uv[0][1],),
+						(uv[1][0], uv[1][1],),
+						(uv[2][0], uv[2][1],),
+					]
+				},),
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Mesh {
+				positions,
+				normals,
+				uvs,
+				indices,
+				material,
+			} => {
+				let len = positions.len();
+				if normals.iter().any(|n| n.len() != len,)
+					|| uvs.iter().any(|uv| uv.len() != len,)
+					|| indices.iter().flatten().any(|&i| i as usize >= len,)
+				{
+					return Err(SceneError::InvalidMesh,);
+				}
+
+				Arc::new(TriangleMesh::new(
+					positions.iter().copied().map(vec3,).collect(),
+					normals
+						.as_ref()
+						.map(|n| n.iter().copied().map(vec3,).collect(),),
+					uvs.as_ref()
+						.map(|uv| uv.iter().map(|uv| (uv[0], uv[1],),).collect(),),
+					indices.clone(),
+					self.material_ref(material,)?,
+				),)
+			},
+			ObjectDesc::Obj { path, material, } => {
+				let default_material = match material {
+					Some(material,) => self.material_ref(material,)?,
+					None => Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8,),),),
+				};
+				Arc::new(obj::load(&self.dir.join(path,), default_material,)?,)
+			},
+			ObjectDesc::Gltf { path, } =>
+				Arc::new(gltf_import::load(&self.dir.join(path,),)?.world,),
+			ObjectDesc::Ply { path, material, } => {
+				let ply = Ply::load(&self.dir.join(path,),)?;
+				let vertex_colors = ply
+					.vertex_color_texture()
+					.map(|texture| Arc::new(texture,) as Arc<dyn Texture,>,);
+				let uses_vertex_colors = vertex_colors.is_some()
+					&& match material {
+						Some(material,) => self.uses_vertex_colors(self.material_desc(material,)?,),
+						None => true,
+					};
+				let mat_ptr: Arc<dyn Material,> = match (material, &vertex_colors,) {
+					(Some(material,), _,) =>
+						self.colored_material_ref(material, vertex_colors.as_ref(),)?,
+					(None, Some(albedo,),) => Arc::new(Lambertian {
+						albedo: albedo.clone(),
+					},),
+					(None, None,) => Arc::new(Lambertian::new(Color::new(0.8, 0.8, 0.8,),),),
+				};
+
+				let mesh = ply.into_mesh(mat_ptr,);
+				Arc::new(if uses_vertex_colors {
+					mesh.with_barycentric_uvs()
+				} else {
+					mesh
+				},)
+			},
+			ObjectDesc::Stl { path, material, } => Arc::new(stl::load(
+				&self.dir.join(path,),
+				self.material_ref(material,)?,
+			)?,),
+			ObjectDesc::Translate { offset, object, } =>
+				Arc::new(Transform::translate(self.object(object,)?, vec3(*offset,),),),
+			ObjectDesc::RotateY { angle, object, } =>
+				Arc::new(Transform::rotate_y(self.object(object,)?, *angle,),),
+			ObjectDesc::Transform {
+				transforms, object,
+			} => {
+				let matrix = transforms
+					.iter()
+					.fold(Mat4::identity(), |matrix, transform| {
+						transform.matrix() * matrix
+					},);
+				if matrix.inverse().is_none() {
+					return Err(SceneError::SingularTransform,);
+				}
+				Arc::new(Transform::new(self.object(object,)?, matrix,),)
+			},
+			ObjectDesc::Animated { keyframes, object, } => {
+				if keyframes.is_empty() {
+					return Err(SceneError::NoKeyframes,);
+				}
+				let keyframes = keyframes
+					.iter()
+					.map(|keyframe| {
+						let rotation = keyframe
+							.rotate
+							.as_ref()
+							.map_or_else(Quat::identity, |rotate| {
+								Quat::from_axis_angle(vec3(rotate.axis,), rotate.angle,)
+							},);
+						Keyframe::new(keyframe.time, vec3(keyframe.translate,), rotation,)
+					},)
+					.collect();
+				Arc::new(AnimatedTransform::new(self.object(object,)?, keyframes,),)
+			},
+			ObjectDesc::ConstantMedium {
+				density,
+				albedo,
+				boundary,
+			} => Arc::new(ConstantMedium::from_texture(
+				self.object(boundary,)?,
+				*density,
+				self.texture(albedo, None,)?,
+			),),
+			ObjectDesc::Sdf {
+				shape,
+				min,
+				max,
+				material,
+			} => Arc::new(SdfHittable::new(
+				shape.sdf(),
+				AABB::new(vec3(*min,), vec3(*max,),),
+				self.material_ref(material,)?,
+			),),
+			ObjectDesc::Union { a, b, } =>
+				Arc::new(Csg::union(self.object(a,)?, self.object(b,)?,),),
+			ObjectDesc::Intersection { a, b, } =>
+				Arc::new(Csg::intersection(self.object(a,)?, self.object(b,)?,),),
+			ObjectDesc::Difference { a, b, } =>
+				Arc::new(Csg::difference(self.object(a,)?, self.object(b,)?,),),
+		},)
+	}
+}
+
+/// Loads a JSON scene file, or imports a `.gltf`/`.glb` file. Relative image and model paths are
+/// resolved against the directory of the file.
+pub fn load(path: &Path,) -> Result<Scene, SceneError,> {
+	if matches!(
+		path.extension().and_then(|e| e.to_str(),),
+		Some("gltf" | "glb")
+	) {
+		return Ok(gltf_import::load(path,)?,);
+	}
+
+	let desc: SceneDesc = serde_json::from_str(&read_to_string(path,)?,)?;
+	let dir = path.parent().unwrap_or_else(|| Path::new("",),);
+
+	let mut builder = Builder {
+		dir,
+		textures: HashMap::new(),
+		vertex_colors: HashSet::new(),
+		materials: HashMap::new(),
+		material_descs: &desc.materials,
+	};
+
+	for (name, texture,) in &desc.textures {
+		let texture: Arc<dyn Texture,> = match texture {
+			TextureDesc::VertexColors => {
+				builder.vertex_colors.insert(name.clone(),);
+				continue;
+			},
+			TextureDesc::Solid { color, } => Arc::new(SolidColor::from(vec3(*color,),),),
+			TextureDesc::Checker { even, odd, } =>
+				Arc::new(CheckerTexture::new(vec3(*even,), vec3(*odd,),),),
+			TextureDesc::Noise { scale, } => Arc::new(NoiseTexture::new(*scale,),),
+			TextureDesc::Image { path, } =>
+				Arc::new(ImageTexture::new(&dir.join(path,).to_string_lossy(),),),
+		};
+		builder.textures.insert(name.clone(), texture,);
+	}
+
+	for (name, material,) in &desc.materials {
+		if !builder.uses_vertex_colors(material,) {
+			let material = builder.material(material, None,)?;
+			builder.materials.insert(name.clone(), material,);
+		}
+	}
+
+	let mut world = HittableList::default();
+	let mut lights = HittableList::default();
+	for object in &desc.objects {
+		let hittable = builder.object(object,)?;
+		if object.is_area_light(&desc.materials,) {
+			lights.add(hittable.clone(),);
+		}
+		world.add(hittable,);
+	}
+
+	let default = Scene::default();
+	let camera = desc.camera;
+
+	Ok(Scene {
+		world,
+		lights,
+		background: desc.background.map_or(default.background, vec3,),
+		lookfrom: camera.lookfrom.map_or(default.lookfrom, vec3,),
+		lookat: camera.lookat.map_or(default.lookat, vec3,),
+		vup: camera.vup.map_or(default.vup, vec3,),
+		vfov: camera.vfov.unwrap_or(default.vfov,),
+		aperture: camera.aperture.unwrap_or(default.aperture,),
+		focus_dist: camera.focus_dist.unwrap_or(default.focus_dist,),
+		aspect_ratio: desc.aspect_ratio.unwrap_or(default.aspect_ratio,),
+		image_width: desc.image_width.unwrap_or(default.image_width,),
+		samples_per_pixel: desc.samples_per_pixel.unwrap_or(default.samples_per_pixel,),
+	},)
+}
@@ -0,0 +1,436 @@
+use std::sync::Arc;
+
+use crate::{
+	_box::Box,
+	aarect::{XYRect, XZRect, YZRect},
+	constantmedium::ConstantMedium,
+	hittable::{Hittable, Transform},
+	hittable_list::HittableList,
+	material::{Dielectric, DiffuseLight, Lambertian, Material, Metal},
+	matrix::Mat4,
+	movingsphere::MovingSphere,
+	sphere::Sphere,
+	texture::{CheckerTexture, ImageTexture, NoiseTexture},
+	util::{random_f64, random_f64_range},
+	vec3::{Color, Point3, Vec3},
+};
+
+/// A world together with the camera and image settings it is meant to be rendered with.
+pub struct Scene {
+	pub world:             HittableList,
+	/// Emitters in `world` that are sampled directly at every diffuse bounce.
+	pub lights:            HittableList,
+	pub background:        Color,
+	pub lookfrom:          Point3,
+	pub lookat:            Point3,
+	pub vup:               Vec3,
+	pub vfov:              f64,
+	pub aperture:          f64,
+	pub focus_dist:        f64,
+	pub aspect_ratio:      f64,
+	pub image_width:       usize,
+	pub samples_per_pixel: i32,
+}
+
+impl Default for Scene {
+	fn default() -> Self {
+		Scene {
+			world:             HittableList::default(),
+			lights:            HittableList::default(),
+			background:        Color::default(),
+			lookfrom:          Point3::new(13.0, 2.0, 3.0,),
+			lookat:            Point3::default(),
+			vup:               Vec3::new(0.0, 1.0, 0.0,),
+			vfov:              40.0,
+			aperture:          0.0,
+			focus_dist:        10.0,
+			aspect_ratio:      16.0 / 9.0,
+			image_width:       400,
+			samples_per_pixel: 100,
+		}
+	}
+}
+
+/// Names of the built-in scenes, in the order they are listed in the usage.
+pub const SCENES: [&str; 7] = [
+	"random_scene",
+	"two_spheres",
+	"two_perlin_spheres",
+	"earth",
+	"simple_light",
+	"cornell_box",
+	"cornell_smoke",
+];
+
+pub fn scene(name: &str,) -> Option<Scene,> {
+	let scene = match name {
+		"random_scene" => Scene {
+			world: random_scene(),
+			background: Color::new(0.70, 0.80, 1.00,),
+			lookfrom: Point3::new(13.0, 2.0, 3.0,),
+			lookat: Point3::new(0.0, 0.0, 0.0,),
+			vfov: 20.0,
+			aperture: 0.1,
+			..Scene::default()
+		},
+		"two_spheres" => Scene {
+			world: two_spheres(),
+			background: Color::new(0.70, 0.80, 1.00,),
+			lookfrom: Point3::new(13.0, 2.0, 3.0,),
+			lookat: Point3::new(0.0, 0.0, 0.0,),
+			vfov: 20.0,
+			..Scene::default()
+		},
+		"two_perlin_spheres" => Scene {
+			world: two_perlin_spheres(),
+			background: Color::new(0.70, 0.80, 1.00,),
+			lookfrom: Point3::new(13.0, 2.0, 3.0,),
+			lookat: Point3::new(0.0, 0.0, 0.0,),
+			vfov: 20.0,
+			..Scene::default()
+		},
+		"earth" => Scene {
+			world: earth(),
+			background: Color::new(0.70, 0.80, 1.00,),
+			lookfrom: Point3::new(13.0, 2.0, 3.0,),
+			lookat: Point3::default(),
+			vfov: 20.0,
+			..Scene::default()
+		},
+		"simple_light" => {
+			let (world, lights,) = simple_light();
+			Scene {
+				world,
+				lights,
+				samples_per_pixel: 400,
+				background: Color::default(),
+				lookfrom: Point3::new(26.0, 3.0, 6.0,),
+				lookat: Point3::new(0.0, 2.0, 0.0,),
+				vfov: 20.0,
+				..Scene::default()
+			}
+		},
+		"cornell_box" => {
+			let (world, lights,) = cornell_box();
+			Scene {
+				world,
+				lights,
+				aspect_ratio: 1.0,
+				image_width: 600,
+				samples_per_pixel: 200,
+				background: Color::default(),
+				lookfrom: Point3::new(278.0, 278.0, -800.0,),
+				lookat: Point3::new(278.0, 278.0, 0.0,),
+				vfov: 40.0,
+				..Scene::default()
+			}
+		},
+		"cornell_smoke" => {
+			let (world, lights,) = cornell_smoke();
+			Scene {
+				world,
+				lights,
+				aspect_ratio: 1.0,
+				image_width: 600,
+				samples_per_pixel: 200,
+				background: Color::default(),
+				lookfrom: Point3::new(278.0, 278.0, -800.0,),
+				lookat: Point3::new(278.0, 278.0, 0.0,),
+				vfov: 40.0,
+				..Scene::default()
+			}
+		},
+		_ => return None,
+	};
+
+	Some(scene,)
+}
+
+fn random_scene() -> HittableList {
+	let mut world = HittableList::default();
+
+	let checker = Arc::new(CheckerTexture::new(
+		Color::new(0.2, 0.3, 0.1,),
+		Color::new(0.9, 0.9, 0.9,),
+	),);
+	let ground_material = Arc::new(Lambertian::from(checker,),);
+	world.add(Arc::new(Sphere::new(
+		Point3::new(0.0, -1000.0, 0.0,),
+		1000.0,
+		ground_material,
+	),),);
+
+	for a in -11 .. 11 {
+		for b in -11 .. 11 {
+			let choose_mat = random_f64();
+			let center = Point3::new(
+				a as f64 + 0.9 * random_f64(),
+				0.2,
+				b as f64 + 0.9 * random_f64(),
+			);
+
+			if (center - Point3::new(4.0, 0.2, 0.0,)).length() > 0.9 {
+				let sphere_material: Arc<dyn Material,>;
+
+				if choose_mat < 0.8 {
+					let albedo = Color::random() * Color::random();
+					sphere_material = Arc::new(Lambertian::new(albedo,),);
+					let center2 = center + Vec3::new(0.0, random_f64_range(0.0, 0.5,), 0.0,);
+					world.add(Arc::new(MovingSphere::new(
+						center,
+						center2,
+						0.0,
+						1.0,
+						0.2,
+						sphere_material,
+					),),);
+				} else if choose_mat < 0.95 {
+					let albedo = Color::random_in_range(0.5, 1.0,);
+					let fuzz = random_f64_range(0.0, 0.5,);
+					sphere_material = Arc::new(Metal::new(albedo, fuzz,),);
+					world.add(Arc::new(Sphere::new(center, 0.2, sphere_material,),),);
+				} else {
+					sphere_material = Arc::new(Dielectric::new(1.5,),);
+					world.add(Arc::new(Sphere::new(center, 0.2, sphere_material,),),);
+				}
+			}
+		}
+	}
+
+	let material1 = Arc::new(Dielectric::new(1.5,),);
+	world.add(Arc::new(Sphere::new(
+		Point3::new(0.0, 1.0, 0.0,),
+		1.0,
+		material1,
+	),),);
+
+	let material2 = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1,),),);
+	world.add(Arc::new(Sphere::new(
+		Point3::new(-4.0, 1.0, 0.0,),
+		1.0,
+		material2,
+	),),);
+
+	let material3 = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5,), 0.0,),);
+	world.add(Arc::new(Sphere::new(
+		Point3::new(4.0, 1.0, 0.0,),
+		1.0,
+		material3,
+	),),);
+
+	world
+}
+
+fn two_spheres() -> HittableList {
+	let mut objects = HittableList::default();
+
+	let checker = Arc::new(CheckerTexture::new(
+		Color::new(0.2, 0.3, 0.1,),
+		Color::new(0.9, 0.9, 0.9,),
+	),);
+	objects.add(Arc::new(Sphere::new(
+		Point3::new(0.0, -10.0, 0.0,),
+		10.0,
+		Arc::new(Lambertian::from(checker.clone(),),),
+	),),);
+	objects.add(Arc::new(Sphere::new(
+		Point3::new(0.0, 10.0, 0.0,),
+		10.0,
+		Arc::new(Lambertian::from(checker,),),
+	),),);
+
+	objects
+}
+
+fn two_perlin_spheres() -> HittableList {
+	let mut objects = HittableList::default();
+
+	let pertext = Arc::new(NoiseTexture::new(4.0,),);
+	objects.add(Arc::new(Sphere::new(
+		Point3::new(0.0, -1000.0, 0.0,),
+		1000.0,
+		Arc::new(Lambertian::from(pertext.clone(),),),
+	),),);
+	objects.add(Arc::new(Sphere::new(
+		Point3::new(0.0, 2.0, 0.0,),
+		2.0,
+		Arc::new(Lambertian::from(pertext,),),
+	),),);
+
+	objects
+}
+
+fn earth() -> HittableList {
+	let earth_texture = Arc::new(ImageTexture::new("earthmap.jpg",),);
+	let earth_surface = Arc::new(Lambertian::from(earth_texture,),);
+	let globe = Arc::new(Sphere::new(Point3::default(), 2.0, earth_surface,),);
+
+	HittableList::new(globe,)
+}
+
+fn simple_light() -> (HittableList, HittableList,) {
+	let mut objects = HittableList::default();
+	let mut lights = HittableList::default();
+
+	let pertext = Arc::new(NoiseTexture::new(4.0,),);
+	objects.add(Arc::new(Sphere::new(
+		Point3::new(0.0, -1000.0, 0.0,),
+		1000.0,
+		Arc::new(Lambertian::from(pertext.clone(),),),
+	),),);
+	objects.add(Arc::new(Sphere::new(
+		Point3::new(0.0, 2.0, 0.0,),
+		2.0,
+		Arc::new(Lambertian::from(pertext,),),
+	),),);
+
+	let difflight = Arc::new(DiffuseLight::from(Color::new(4.0, 4.0, 4.0,),),);
+	let rect: Arc<dyn Hittable,> =
+		Arc::new(XYRect::new(3.0, 5.0, 1.0, 3.0, -2.0, difflight.clone(),),);
+	let sphere: Arc<dyn Hittable,> =
+		Arc::new(Sphere::new(Point3::new(0.0, 7.0, 0.0,), 2.0, difflight,),);
+	objects.add(rect.clone(),);
+	objects.add(sphere.clone(),);
+	lights.add(rect,);
+	lights.add(sphere,);
+
+	(objects, lights,)
+}
+
+fn cornell_box() -> (HittableList, HittableList,) {
+	let mut objects = HittableList::default();
+	let mut lights = HittableList::default();
+
+	let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05,),),);
+	let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73,),),);
+	let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15,),),);
+	let light = Arc::new(DiffuseLight::from(Color::new(15.0, 15.0, 15.0,),),);
+
+	objects.add(Arc::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green,),),);
+	objects.add(Arc::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red,),),);
+	let light: Arc<dyn Hittable,> =
+		Arc::new(XZRect::new(213.0, 343.0, 227.0, 332.0, 554.0, light,),);
+	objects.add(light.clone(),);
+	lights.add(light,);
+	objects.add(Arc::new(XZRect::new(
+		0.0,
+		555.0,
+		0.0,
+		555.0,
+		0.0,
+		white.clone(),
+	),),);
+	objects.add(Arc::new(XZRect::new(
+		0.0,
+		555.0,
+		0.0,
+		555.0,
+		555.0,
+		white.clone(),
+	),),);
+	objects.add(Arc::new(XYRect::new(
+		0.0,
+		555.0,
+		0.0,
+		555.0,
+		555.0,
+		white.clone(),
+	),),);
+
+	let box1 = Arc::new(Box::new(
+		&Point3::new(0.0, 0.0, 0.0,),
+		&Point3::new(165.0, 330.0, 165.0,),
+		white.clone(),
+	),);
+	let box1 = Arc::new(Transform::new(
+		box1,
+		Mat4::translation(Vec3::new(265.0, 0.0, 295.0,),) * Mat4::rotation(Vec3::new(0.0, 1.0, 0.0,), 15.0,),
+	),);
+	objects.add(box1,);
+
+	let box2 = Arc::new(Box::new(
+		&Point3::new(0.0, 0.0, 0.0,),
+		&Point3::new(165.0, 165.0, 165.0,),
+		white,
+	),);
+	let box2 = Arc::new(Transform::new(
+		box2,
+		Mat4::translation(Vec3::new(130.0, 0.0, 65.0,),) * Mat4::rotation(Vec3::new(0.0, 1.0, 0.0,), -18.0,),
+	),);
+	objects.add(box2,);
+
+	(objects, lights,)
+}
+
+fn cornell_smoke() -> (HittableList, HittableList,) {
+	let mut objects = HittableList::default();
+	let mut lights = HittableList::default();
+
+	let red = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05,),),);
+	let white = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73,),),);
+	let green = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15,),),);
+	let light = Arc::new(DiffuseLight::from(Color::new(7.0, 7.0, 7.0,),),);
+
+	objects.add(Arc::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 555.0, green,),),);
+	objects.add(Arc::new(YZRect::new(0.0, 555.0, 0.0, 555.0, 0.0, red,),),);
+	let light: Arc<dyn Hittable,> =
+		Arc::new(XZRect::new(113.0, 443.0, 127.0, 432.0, 554.0, light,),);
+	objects.add(light.clone(),);
+	lights.add(light,);
+	objects.add(Arc::new(XZRect::new(
+		0.0,
+		555.0,
+		0.0,
+		555.0,
+		0.0,
+		white.clone(),
+	),),);
+	objects.add(Arc::new(XZRect::new(
+		0.0,
+		555.0,
+		0.0,
+		555.0,
+		555.0,
+		white.clone(),
+	),),);
+	objects.add(Arc::new(XYRect::new(
+		0.0,
+		555.0,
+		0.0,
+		555.0,
+		555.0,
+		white.clone(),
+	),),);
+
+	let box1 = Arc::new(Box::new(
+		&Point3::new(0.0, 0.0, 0.0,),
+		&Point3::new(165.0, 330.0, 165.0,),
+		white.clone(),
+	),);
+	let box1 = Arc::new(Transform::new(
+		box1,
+		Mat4::translation(Vec3::new(265.0, 0.0, 295.0,),) * Mat4::rotation(Vec3::new(0.0, 1.0, 0.0,), 15.0,),
+	),);
+	objects.add(Arc::new(ConstantMedium::from_color(
+		box1,
+		0.01,
+		Color::default(),
+	),),);
+
+	let box2 = Arc::new(Box::new(
+		&Point3::new(0.0, 0.0, 0.0,),
+		&Point3::new(165.0, 165.0, 165.0,),
+		white,
+	),);
+	let box2 = Arc::new(Transform::new(
+		box2,
+		Mat4::translation(Vec3::new(130.0, 0.0, 65.0,),) * Mat4::rotation(Vec3::new(0.0, 1.0, 0.0,), -18.0,),
+	),);
+	objects.add(Arc::new(ConstantMedium::from_color(
+		box2,
+		0.01,
+		Color::new(1.0, 1.0, 1.0,),
+	),),);
+
+	(objects, lights,)
+}
@@ -0,0 +1,256 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+	aabb::AABB,
+	hittable::{HitRecord, Hittable},
+	material::Material,
+	ray::Ray,
+	vec3::{Point3, unit_vector, Vec3},
+};
+
+/// Steps after which a ray is assumed to miss, this only happens when grazing a surface.
+const MAX_STEPS: usize = 512;
+/// Distance at which a ray counts as touching the surface.
+const HIT_DISTANCE: f64 = 1e-5;
+/// Offset of the samples that estimate the gradient.
+const GRADIENT_STEP: f64 = 1e-6;
+
+/// A signed distance to a surface, negative inside it. The distance may be underestimated, but
+/// never overestimated, or rays step through the surface.
+pub trait Sdf: Send + Sync {
+	fn distance(&self, p: &Point3,) -> f64;
+}
+
+impl<F: Fn(&Point3,) -> f64 + Send + Sync,> Sdf for F {
+	fn distance(&self, p: &Point3,) -> f64 { self(p,) }
+}
+
+/// Renders the surface of a distance function by sphere tracing: the ray steps forward by the
+/// distance to the surface until it gets close enough. Only the part inside `bbox` is traced.
+/// Normals come from the gradient of the distance, UVs from the direction of the normal like on
+/// a sphere.
+pub struct SdfHittable {
+	sdf:     Arc<dyn Sdf,>,
+	bbox:    AABB,
+	mat_ptr: Arc<dyn Material,>,
+}
+
+impl SdfHittable {
+	pub fn new(sdf: Arc<dyn Sdf,>, bbox: AABB, mat_ptr: Arc<dyn Material,>,) -> Self {
+		SdfHittable {
+			sdf, bbox, mat_ptr,
+		}
+	}
+
+	/// The gradient estimated from four samples at the corners of a tetrahedron.
+	fn normal(&self, p: &Point3,) -> Vec3 {
+		let corners = [
+			Vec3::new(1.0, -1.0, -1.0,),
+			Vec3::new(-1.0, -1.0, 1.0,),
+			Vec3::new(-1.0, 1.0, -1.0,),
+			Vec3::new(1.0, 1.0, 1.0,),
+		];
+
+		let mut gradient = Vec3::default();
+		for corner in &corners {
+			gradient += self.sdf.distance(&(*p + GRADIENT_STEP * *corner),) * *corner;
+		}
+		unit_vector(&gradient,)
+	}
+
+	/// The part of `t_min ..= t_max` where the ray is inside the bounding box.
+	fn clip(&self, r: &Ray, mut t_min: f64, mut t_max: f64,) -> Option<(f64, f64,),> {
+		for a in 0 .. 3 {
+			let inv_d = 1.0 / r.direction()[a];
+			let mut t0 = (self.bbox.min()[a] - r.origin()[a]) * inv_d;
+			let mut t1 = (self.bbox.max()[a] - r.origin()[a]) * inv_d;
+			if inv_d < 0.0 {
+				std::mem::swap(&mut t0, &mut t1,);
+			}
+			t_min = t_min.max(t0,);
+			t_max = t_max.min(t1,);
+			if t_max < t_min {
+				return None;
+			}
+		}
+		Some((t_min, t_max,),)
+	}
+}
+
+impl Hittable for SdfHittable {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let (mut t, t_end,) = self.clip(r, t_min, t_max,)?;
+		let speed = r.direction().length();
+
+		// Marching by the absolute distance also finds the way out for rays that start inside.
+		for _ in 0 .. MAX_STEPS {
+			let p = r.at(t,);
+			let distance = self.sdf.distance(&p,).abs();
+
+			if distance < HIT_DISTANCE {
+				let outward_normal = self.normal(&p,);
+				let theta = (-outward_normal.y()).acos();
+				let phi = -outward_normal.z().atan2(outward_normal.x(),) + PI;
+
+				let mut rec = HitRecord {
+					p,
+					normal: Default::default(),
+					mat_ptr: self.mat_ptr.clone(),
+					t,
+					u: phi / (2.0 * PI),
+					v: theta / PI,
+					front_face: false,
+				};
+				rec.set_face_normal(r, &outward_normal,);
+				return Some(rec,);
+			}
+
+			t += distance / speed;
+			if t > t_end {
+				return None;
+			}
+		}
+
+		None
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> { Some(self.bbox.clone(),) }
+}
+
+/// A sphere around the origin.
+pub struct SdfSphere {
+	pub radius: f64,
+}
+
+impl Sdf for SdfSphere {
+	fn distance(&self, p: &Point3,) -> f64 { p.length() - self.radius }
+}
+
+/// A box around the origin, reaching `half_extents` along each axis.
+pub struct SdfBox {
+	pub half_extents: Vec3,
+}
+
+impl Sdf for SdfBox {
+	fn distance(&self, p: &Point3,) -> f64 {
+		let mut outside = Vec3::default();
+		let mut inside = f64::NEG_INFINITY;
+		for c in 0 .. 3 {
+			let q = p[c].abs() - self.half_extents[c];
+			outside[c] = q.max(0.0,);
+			inside = inside.max(q,);
+		}
+		outside.length() + inside.min(0.0,)
+	}
+}
+
+/// A torus around the y axis.
+pub struct SdfTorus {
+	pub major_radius: f64,
+	pub minor_radius: f64,
+}
+
+impl Sdf for SdfTorus {
+	fn distance(&self, p: &Point3,) -> f64 {
+		let ring = (p.x() * p.x() + p.z() * p.z()).sqrt() - self.major_radius;
+		(ring * ring + p.y() * p.y()).sqrt() - self.minor_radius
+	}
+}
+
+/// The Mandelbulb fractal, which fits in a sphere of radius 1.2 around the origin for the usual
+/// power of 8. The distance is the standard estimate from the running derivative.
+pub struct Mandelbulb {
+	pub power:      f64,
+	pub iterations: usize,
+}
+
+impl Sdf for Mandelbulb {
+	fn distance(&self, p: &Point3,) -> f64 {
+		let mut z = *p;
+		let mut dr = 1.0;
+		let mut r = z.length();
+
+		for _ in 0 .. self.iterations {
+			if r > 2.0 {
+				break;
+			}
+
+			let theta = (z.z() / r).acos() * self.power;
+			let phi = z.y().atan2(z.x(),) * self.power;
+			dr = r.powf(self.power - 1.0,) * self.power * dr + 1.0;
+
+			let zr = r.powf(self.power,);
+			z = zr
+				* Vec3::new(
+					theta.sin() * phi.cos(),
+					theta.sin() * phi.sin(),
+					theta.cos(),
+				) + *p;
+			r = z.length();
+		}
+
+		0.5 * r.ln() * r / dr
+	}
+}
+
+/// Moves a distance function by `offset`.
+pub struct Translate {
+	pub sdf:    Arc<dyn Sdf,>,
+	pub offset: Vec3,
+}
+
+impl Sdf for Translate {
+	fn distance(&self, p: &Point3,) -> f64 { self.sdf.distance(&(*p - self.offset),) }
+}
+
+/// The union of two distance functions, blended over a distance of about `k` where they meet.
+pub struct SmoothUnion {
+	pub a: Arc<dyn Sdf,>,
+	pub b: Arc<dyn Sdf,>,
+	pub k: f64,
+}
+
+impl Sdf for SmoothUnion {
+	fn distance(&self, p: &Point3,) -> f64 {
+		let a = self.a.distance(p,);
+		let b = self.b.distance(p,);
+		let h = (0.5 + 0.5 * (b - a) / self.k).clamp(0.0, 1.0,);
+		b + h * (a - b) - self.k * h * (1.0 - h)
+	}
+}
+
+/// Twists a distance function around the y axis by `rate` radians per unit of height.
+pub struct Twist {
+	pub sdf:  Arc<dyn Sdf,>,
+	pub rate: f64,
+}
+
+impl Sdf for Twist {
+	/// Twisting stretches space further away from the axis, so the distance is shrunk by the
+	/// local stretch to keep the steps from overshooting.
+	fn distance(&self, p: &Point3,) -> f64 {
+		let (sin, cos,) = (self.rate * p.y()).sin_cos();
+		let q = Vec3::new(cos * p.x() - sin * p.z(), p.y(), sin * p.x() + cos * p.z(),);
+		let radius = (p.x() * p.x() + p.z() * p.z()).sqrt();
+		self.sdf.distance(&q,) / (1.0 + (self.rate * radius).powi(2,)).sqrt()
+	}
+}
+
+/// Repeats a distance function endlessly along the axes with a nonzero `period`, copies sit at
+/// every multiple of the period. The copy around the origin has to fit inside one cell.
+pub struct Repeat {
+	pub sdf:    Arc<dyn Sdf,>,
+	pub period: Vec3,
+}
+
+impl Sdf for Repeat {
+	fn distance(&self, p: &Point3,) -> f64 {
+		let mut q = *p;
+		for c in 0 .. 3 {
+			if self.period[c] != 0.0 {
+				q[c] -= self.period[c] * (p[c] / self.period[c]).round();
+			}
+		}
+		self.sdf.distance(&q,)
+	}
+}
@@ -1,20 +1,22 @@
 use std::f64::consts::PI;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::aabb::AABB;
 use crate::hittable::{HitRecord, Hittable};
 use crate::material::Material;
+use crate::onb::Onb;
 use crate::ray::Ray;
-use crate::vec3::{dot, Point3, Vec3};
+use crate::sampler::Sampler;
+use crate::vec3::{dot, sample_to_sphere, sample_unit_vector, Point3, Vec3};
 
 pub struct Sphere {
 	pub center: Point3,
 	pub radius: f64,
-	pub mat_ptr: Rc<dyn Material>,
+	pub mat_ptr: Arc<dyn Material>,
 }
 
 impl Sphere {
-	pub fn new(center: Vec3, radius: f64, mat_ptr: Rc<dyn Material>) -> Self {
+	pub fn new(center: Vec3, radius: f64, mat_ptr: Arc<dyn Material>) -> Self {
 		Sphere {
 			center,
 			radius,
@@ -77,4 +79,31 @@ impl Hittable for Sphere {
 			self.center + Vec3::new(self.radius, self.radius, self.radius),
 		))
 	}
+	/// Directions are spread uniformly over the cone the sphere covers as seen from `origin`, or
+	/// over all directions from an `origin` inside the sphere.
+	fn pdf_value(&self, origin: &Point3, direction: &Vec3) -> f64 {
+		if self.hit(&Ray::new(*origin, *direction, 0.0), 0.001, f64::INFINITY).is_none() {
+			return 0.0;
+		}
+
+		let sin2_theta_max = self.radius * self.radius / (self.center - *origin).length_squared();
+		if sin2_theta_max >= 1.0 {
+			return 1.0 / (4.0 * PI);
+		}
+
+		// `1 - cos_theta_max`, written so that it does not cancel to zero for far away origins.
+		let solid_angle = 2.0 * PI * sin2_theta_max / (1.0 + (1.0 - sin2_theta_max).sqrt());
+		1.0 / solid_angle
+	}
+
+	fn random(&self, origin: &Point3, sampler: &mut dyn Sampler) -> Vec3 {
+		let direction = self.center - *origin;
+		let u = sampler.get_2d();
+		if direction.length_squared() <= self.radius * self.radius {
+			return sample_unit_vector(u);
+		}
+
+		let uvw = Onb::build_from_w(&direction);
+		uvw.local(&sample_to_sphere(self.radius, direction.length_squared(), u))
+	}
 }
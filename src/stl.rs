@@ -0,0 +1,190 @@
+use std::{
+	convert::TryInto,
+	collections::HashMap,
+	fmt::{Display, Formatter},
+	fs::read,
+	io,
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use crate::{material::Material, mesh::TriangleMesh, vec3::Point3};
+
+/// Everything that can go wrong while loading an STL file.
+#[derive(Debug,)]
+pub enum StlError {
+	Io(PathBuf, io::Error,),
+	Parse(PathBuf, String,),
+}
+
+impl Display for StlError {
+	fn fmt(&self, f: &mut Formatter<'_,>,) -> std::fmt::Result {
+		match self {
+			StlError::Io(file, e,) => write!(f, "{}: {}", file.display(), e),
+			StlError::Parse(file, message,) => write!(f, "{}: {}", file.display(), message),
+		}
+	}
+}
+
+/// Reads the triangles of a binary STL file: an 80 byte header, the triangle count and 50 bytes
+/// per triangle holding the facet normal, three vertices and an attribute word.
+fn parse_binary(bytes: &[u8],) -> Vec<[Point3; 3],> {
+	let float = |offset: usize| {
+		f32::from_le_bytes(bytes[offset .. offset + 4].try_into().unwrap(),) as f64
+	};
+	let count = u32::from_le_bytes(bytes[80 .. 84].try_into().unwrap(),) as usize;
+
+	(0 .. count)
+		.map(|triangle| {
+			let start = 84 + 50 * triangle + 12;
+			[0, 1, 2,].map(|vertex| {
+				let offset = start + 12 * vertex;
+				Point3::new(float(offset,), float(offset + 4,), float(offset + 8,),)
+			},)
+		},)
+		.collect()
+}
+
+fn parse_ascii(source: &str,) -> Result<Vec<[Point3; 3],>, String,> {
+	let mut triangles = Vec::new();
+	let mut vertices = Vec::new();
+
+	for (number, line,) in source.lines().enumerate() {
+		let mut tokens = line.split_whitespace();
+		match tokens.next() {
+			Some("vertex",) => {
+				let mut coordinate = || -> Result<f64, String,> {
+					let token = tokens
+						.next()
+						.ok_or_else(|| format!("line {}: `vertex` needs 3 values", number + 1),)?;
+					token.parse().map_err(|_| format!("line {}: `{}` is not a number", number + 1, token),)
+				};
+				vertices.push(Point3::new(coordinate()?, coordinate()?, coordinate()?,),);
+			},
+			Some("endloop",) => {
+				if vertices.len() != 3 {
+					return Err(format!("line {}: a facet needs 3 vertices, got {}", number + 1, vertices.len()),);
+				}
+				triangles.push([vertices[0], vertices[1], vertices[2],],);
+				vertices.clear();
+			},
+			Some("solid" | "facet" | "outer" | "endfacet" | "endsolid",) | None => {},
+			Some(keyword,) => return Err(format!("line {}: unknown keyword `{}`", number + 1, keyword),),
+		}
+	}
+
+	Ok(triangles,)
+}
+
+/// Loads an ASCII or binary STL file as a flat shaded `TriangleMesh`, merging vertices with
+/// identical coordinates. Binary files are recognized by their size, as some exporters start
+/// them with `solid` as well.
+pub fn load(path: &Path, mat_ptr: Arc<dyn Material,>,) -> Result<TriangleMesh, StlError,> {
+	let bytes = read(path,).map_err(|e| StlError::Io(path.to_path_buf(), e,),)?;
+
+	let binary = bytes.len() >= 84
+		&& bytes.len() == 84 + 50 * u32::from_le_bytes(bytes[80 .. 84].try_into().unwrap(),) as usize;
+	let triangles = if binary {
+		parse_binary(&bytes,)
+	} else {
+		let source = std::str::from_utf8(&bytes,)
+			.map_err(|_| StlError::Parse(path.to_path_buf(), "not a valid STL file".to_string(),),)?;
+		parse_ascii(source,).map_err(|message| StlError::Parse(path.to_path_buf(), message,),)?
+	};
+
+	let mut positions = Vec::new();
+	let mut lookup = HashMap::new();
+	let indices = triangles
+		.iter()
+		.map(|triangle| {
+			triangle.map(|vertex| {
+				*lookup.entry(vertex.e.map(f64::to_bits,),).or_insert_with(|| {
+					positions.push(vertex,);
+					(positions.len() - 1) as u32
+				},)
+			},)
+		},)
+		.collect();
+
+	Ok(TriangleMesh::new(positions, None, None, indices, mat_ptr,),)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{material::Lambertian, util::temp_file, vec3::Color};
+
+	const FACET: &str = "facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 0 0
+    vertex 0 1 0
+  endloop
+endfacet
+";
+
+	fn load_bytes(name: &str, bytes: &[u8],) -> Result<TriangleMesh, StlError,> {
+		let path = temp_file(name, bytes,);
+		let result = load(&path, Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5,),),),);
+		std::fs::remove_file(path,).unwrap();
+		result
+	}
+
+	/// A binary file with a header that starts like an ASCII one, as some exporters write it.
+	fn binary(triangles: &[[[f32; 3]; 3]],) -> Vec<u8,> {
+		let mut bytes = b"solid binary".to_vec();
+		bytes.resize(80, 0,);
+		bytes.extend_from_slice(&(triangles.len() as u32).to_le_bytes(),);
+		for triangle in triangles {
+			bytes.extend_from_slice(&[0; 12],);
+			for c in triangle.iter().flatten() {
+				bytes.extend_from_slice(&c.to_le_bytes(),);
+			}
+			bytes.extend_from_slice(&[0; 2],);
+		}
+		bytes
+	}
+
+	#[test]
+	fn parses_ascii_facets() {
+		let triangles = parse_ascii(&format!("solid test\n{}{}endsolid test\n", FACET, FACET),).unwrap();
+		assert_eq!(triangles.len(), 2);
+		assert_eq!(triangles[0][1].e, [1.0, 0.0, 0.0,]);
+	}
+
+	#[test]
+	fn rejects_malformed_ascii() {
+		let cases = [
+			"outer loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\n",
+			"outer loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 1 0\nvertex 1 1 0\nendloop\n",
+			"outer loop\nvertex 0 0\n",
+			"outer loop\nvertex 0 zero 0\n",
+			"triangle 0 0 0\n",
+		];
+
+		for source in cases {
+			assert!(parse_ascii(source,).is_err(), "accepted {:?}", source);
+		}
+	}
+
+	#[test]
+	fn merges_shared_vertices() {
+		let bytes = binary(&[
+			[[0.0, 0.0, 0.0,], [1.0, 0.0, 0.0,], [1.0, 1.0, 0.0,],],
+			[[0.0, 0.0, 0.0,], [1.0, 1.0, 0.0,], [0.0, 1.0, 0.0,],],
+		],);
+		let mesh = load_bytes("quad.stl", &bytes,).unwrap();
+		assert_eq!(mesh.positions.len(), 4);
+		assert_eq!(mesh.indices.len(), 2);
+	}
+
+	#[test]
+	fn rejects_truncated_binary_files() {
+		let mut bytes = binary(&[[[0.0, 0.0, 0.0,], [1.0, 0.0, 0.0,], [0.0, 1.0, 0.0,],],],);
+		bytes[81] = 0xff;
+		assert!(matches!(load_bytes("truncated.stl", &bytes,), Err(StlError::Parse(..))));
+		bytes[81] = 0;
+		let cut = bytes.len() - 10;
+		assert!(matches!(load_bytes("cut.stl", &bytes[.. cut],), Err(StlError::Parse(..))));
+	}
+}
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use image::{GenericImageView, open};
 
@@ -8,7 +8,7 @@ use crate::{
 	vec3::{Color, Point3, Vec3},
 };
 
-pub trait Texture {
+pub trait Texture: Send + Sync {
 	fn value(&self, u: f64, v: f64, p: &Point3,) -> Color;
 }
 
@@ -38,15 +38,15 @@ impl Texture for SolidColor {
 }
 
 pub struct CheckerTexture {
-	even: Rc<dyn Texture,>,
-	odd:  Rc<dyn Texture,>,
+	even: Arc<dyn Texture,>,
+	odd:  Arc<dyn Texture,>,
 }
 
 impl CheckerTexture {
 	pub fn new(even: Color, odd: Color,) -> Self {
 		CheckerTexture {
-			even: Rc::new(SolidColor::from(even,),),
-			odd:  Rc::new(SolidColor::from(odd,),),
+			even: Arc::new(SolidColor::from(even,),),
+			odd:  Arc::new(SolidColor::from(odd,),),
 		}
 	}
 }
@@ -114,6 +114,18 @@ impl ImageTexture {
 			bytes_per_scanline,
 		}
 	}
+
+	/// Wraps pixels that are already decoded, `data` holds three bytes per pixel, top row first.
+	pub fn from_rgb8(width: usize, height: usize, data: Vec<u8,>,) -> Self {
+		assert_eq!(data.len(), width * height * ImageTexture::BYTES_PER_PIXEL, "ImageTexture size mismatch");
+
+		ImageTexture {
+			data: Some(data,),
+			width,
+			height,
+			bytes_per_scanline: ImageTexture::BYTES_PER_PIXEL * width,
+		}
+	}
 }
 
 impl Texture for ImageTexture {
@@ -148,3 +160,34 @@ impl Texture for ImageTexture {
 		}
 	}
 }
+
+/// Per-vertex colors of a triangle mesh, blended with the barycentric coordinates of each hit.
+/// The mesh must report those in place of UVs, see `TriangleMesh::with_barycentric_uvs`: `u`
+/// lies in `2i ..= 2i + 1` for triangle `i`, `u - 2i` and `v` are the weights of its second and
+/// third corner.
+pub struct VertexColorTexture {
+	colors:  Vec<Color,>,
+	indices: Vec<[u32; 3],>,
+}
+
+impl VertexColorTexture {
+	/// `indices` are the triangles of the mesh, in the order they were given to it.
+	pub fn new(colors: Vec<Color,>, indices: Vec<[u32; 3],>,) -> Self {
+		VertexColorTexture { colors, indices, }
+	}
+}
+
+impl Texture for VertexColorTexture {
+	fn value(&self, u: f64, v: f64, _p: &Point3,) -> Color {
+		if self.indices.is_empty() {
+			return Color::new(0.0, 1.0, 1.0,);
+		}
+
+		let index = ((0.5 * u).max(0.0,) as usize).min(self.indices.len() - 1,);
+		let b1 = clamp(u - 2.0 * index as f64, 0.0, 1.0,);
+		let b2 = clamp(v, 0.0, 1.0 - b1,);
+		let [c0, c1, c2,] = self.indices[index].map(|i| self.colors[i as usize],);
+
+		(1.0 - b1 - b2) * c0 + b1 * c1 + b2 * c2
+	}
+}
@@ -0,0 +1,330 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+	aabb::AABB,
+	hittable::{HitRecord, Hittable},
+	material::Material,
+	onb::Onb,
+	ray::Ray,
+	vec3::{dot, Point3, unit_vector, Vec3},
+};
+
+/// Coefficients closer to zero than this are treated as zero by the polynomial solvers.
+const EPSILON: f64 = 1e-9;
+
+/// A torus around `center` whose tube of `minor_radius` circles `axis` at `major_radius`. The
+/// `u` coordinate runs around the axis and `v` around the tube.
+pub struct Torus {
+	center:       Point3,
+	major_radius: f64,
+	minor_radius: f64,
+	mat_ptr:      Arc<dyn Material,>,
+	onb:          Onb,
+}
+
+impl Torus {
+	pub fn new(
+		center: Point3,
+		axis: Vec3,
+		major_radius: f64,
+		minor_radius: f64,
+		mat_ptr: Arc<dyn Material,>,
+	) -> Self {
+		Torus {
+			center,
+			major_radius,
+			minor_radius,
+			mat_ptr,
+			onb: Onb::build_from_w(&axis,),
+		}
+	}
+}
+
+impl Hittable for Torus {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let mut o = self.onb.to_local(&(r.origin() - self.center),);
+		let d = self.onb.to_local(&r.direction(),);
+		let length = d.length();
+		let d = d / length;
+
+		// The quartic loses precision quickly far away from the torus, so the origin is first
+		// moved up to the sphere around it.
+		let outer = self.major_radius + self.minor_radius;
+		let half_b = dot(&o, &d,);
+		let discriminant = half_b * half_b - (o.length_squared() - outer * outer);
+		if discriminant < 0.0 {
+			return None;
+		}
+		let shift = (-half_b - discriminant.sqrt()).max(0.0,);
+		o += shift * d;
+
+		// (|p|² + R² - r²)² = 4R²(x² + y²) along p = o + s d.
+		let r2 = self.major_radius * self.major_radius;
+		let k = 2.0 * dot(&o, &d,);
+		let l = o.length_squared() + r2 - self.minor_radius * self.minor_radius;
+		let g = 4.0 * r2 * (d.x() * d.x() + d.y() * d.y());
+		let h = 8.0 * r2 * (o.x() * d.x() + o.y() * d.y());
+		let i = 4.0 * r2 * (o.x() * o.x() + o.y() * o.y());
+		let coefficients = [
+			l * l - i,
+			2.0 * k * l - h,
+			k * k + 2.0 * l - g,
+			2.0 * k,
+			1.0,
+		];
+
+		let (roots, count,) = solve_quartic(&coefficients,);
+		let (s, t,) = roots[.. count]
+			.iter()
+			.map(|&s| (s, (s + shift) / length,),)
+			.filter(|&(_, t,)| t >= t_min && t <= t_max,)
+			.min_by(|a, b| a.1.partial_cmp(&b.1,).unwrap(),)?;
+
+		let local = o + s * d;
+		let ring = (local.x() * local.x() + local.y() * local.y()).sqrt();
+		let spine = Vec3::new(local.x(), local.y(), 0.0,) * (self.major_radius / ring);
+		let outward_normal = unit_vector(&self.onb.local(&(local - spine),),);
+
+		let mut rec = HitRecord {
+			p: r.at(t,),
+			normal: Default::default(),
+			mat_ptr: self.mat_ptr.clone(),
+			t,
+			u: 0.5 + local.y().atan2(local.x(),) / (2.0 * PI),
+			v: 0.5 + local.z().atan2(ring - self.major_radius,) / (2.0 * PI),
+			front_face: false,
+		};
+		rec.set_face_normal(r, &outward_normal,);
+
+		Some(rec,)
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> {
+		let w = self.onb.w();
+		let mut extent = Vec3::default();
+		for c in 0 .. 3 {
+			extent[c] =
+				self.major_radius * (1.0 - w[c] * w[c]).max(0.0,).sqrt() + self.minor_radius;
+		}
+
+		Some(AABB::new(self.center - extent, self.center + extent,),)
+	}
+}
+
+/// The real roots of `c[2] x² + c[1] x + c[0]`.
+fn solve_quadric(c: &[f64; 3],) -> ([f64; 2], usize,) {
+	let p = c[1] / (2.0 * c[2]);
+	let q = c[0] / c[2];
+	let discriminant = p * p - q;
+
+	if discriminant.abs() < EPSILON {
+		([-p, 0.0,], 1,)
+	} else if discriminant < 0.0 {
+		([0.0; 2], 0,)
+	} else {
+		let sqrtd = discriminant.sqrt();
+		([sqrtd - p, -sqrtd - p,], 2,)
+	}
+}
+
+/// The real roots of `c[3] x³ + c[2] x² + c[1] x + c[0]`, after Cardano.
+fn solve_cubic(c: &[f64; 4],) -> ([f64; 3], usize,) {
+	let a = c[2] / c[3];
+	let b = c[1] / c[3];
+	let c = c[0] / c[3];
+
+	// Substituting x = y - a/3 gives the depressed cubic y³ + 3py + 2q.
+	let p = (b - a * a / 3.0) / 3.0;
+	let q = (2.0 / 27.0 * a * a * a - a * b / 3.0 + c) / 2.0;
+	let discriminant = q * q + p * p * p;
+
+	let (mut roots, count,) = if discriminant.abs() < EPSILON {
+		if q.abs() < EPSILON {
+			([0.0; 3], 1,)
+		} else {
+			let u = (-q).cbrt();
+			([2.0 * u, -u, 0.0,], 2,)
+		}
+	} else if discriminant < 0.0 {
+		// Three real roots, found with the trigonometric method.
+		let phi = (-q / (-p * p * p).sqrt()).clamp(-1.0, 1.0,).acos() / 3.0;
+		let t = 2.0 * (-p).sqrt();
+		(
+			[
+				t * phi.cos(),
+				-t * (phi + PI / 3.0).cos(),
+				-t * (phi - PI / 3.0).cos(),
+			],
+			3,
+		)
+	} else {
+		let sqrtd = discriminant.sqrt();
+		([(sqrtd - q).cbrt() - (sqrtd + q).cbrt(), 0.0, 0.0,], 1,)
+	};
+
+	for root in &mut roots[.. count] {
+		*root -= a / 3.0;
+	}
+	(roots, count,)
+}
+
+/// The real roots of `c[4] x⁴ + ... + c[0]`, after Ferrari. The roots are polished with a few
+/// Newton steps, since the reduction to a cubic costs a lot of precision.
+fn solve_quartic(c: &[f64; 5],) -> ([f64; 4], usize,) {
+	let a = c[3] / c[4];
+	let b = c[2] / c[4];
+	let cc = c[1] / c[4];
+	let d = c[0] / c[4];
+
+	// Substituting x = y - a/4 gives the depressed quartic y⁴ + py² + qy + r.
+	let a2 = a * a;
+	let p = -3.0 / 8.0 * a2 + b;
+	let q = a2 * a / 8.0 - a * b / 2.0 + cc;
+	let r = -3.0 / 256.0 * a2 * a2 + a2 * b / 16.0 - a * cc / 4.0 + d;
+
+	let mut roots = [0.0; 4];
+	let mut count = 0;
+
+	if r.abs() < EPSILON {
+		// y (y³ + py + q) = 0
+		let (cubic, n,) = solve_cubic(&[q, p, 0.0, 1.0,],);
+		roots[.. n].copy_from_slice(&cubic[.. n],);
+		roots[n] = 0.0;
+		count = n + 1;
+	} else {
+		// Any root of the resolvent cubic splits the quartic into two quadrics.
+		let (cubic, _,) = solve_cubic(&[r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0,],);
+		let z = cubic[0];
+
+		let u = z * z - r;
+		let v = 2.0 * z - p;
+		let u = if u.abs() < EPSILON {
+			0.0
+		} else if u > 0.0 {
+			u.sqrt()
+		} else {
+			return (roots, 0,);
+		};
+		let v = if v.abs() < EPSILON {
+			0.0
+		} else if v > 0.0 {
+			v.sqrt()
+		} else {
+			return (roots, 0,);
+		};
+
+		for &(c0, c1,) in &[
+			(z - u, if q < 0.0 { -v } else { v },),
+			(z + u, if q < 0.0 { v } else { -v },),
+		] {
+			let (quadric, n,) = solve_quadric(&[c0, c1, 1.0,],);
+			roots[count .. count + n].copy_from_slice(&quadric[.. n],);
+			count += n;
+		}
+	}
+
+	for root in &mut roots[.. count] {
+		*root -= a / 4.0;
+
+		for _ in 0 .. 2 {
+			let x = *root;
+			let f = (((x + a) * x + b) * x + cc) * x + d;
+			let df = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + cc;
+			if df.abs() > EPSILON {
+				*root -= f / df;
+			}
+		}
+	}
+	(roots, count,)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::convert::TryInto;
+
+	use super::*;
+
+	/// The coefficients of the monic polynomial with real `roots`, times a factor `x² + q` with
+	/// complex roots for each of `complex`, lowest degree first.
+	fn from_roots(roots: &[f64], complex: &[f64],) -> [f64; 5] {
+		let mut c = vec![1.0];
+		let mut multiply = |factor: &[f64]| {
+			let mut product = vec![0.0; c.len() + factor.len() - 1];
+			for (i, a,) in c.iter().enumerate() {
+				for (j, b,) in factor.iter().enumerate() {
+					product[i + j] += a * b;
+				}
+			}
+			c = product;
+		};
+		for &root in roots {
+			multiply(&[-root, 1.0,],);
+		}
+		for &q in complex {
+			multiply(&[q, 0.0, 1.0,],);
+		}
+		c.try_into().unwrap()
+	}
+
+	/// Checks that the roots of `c` are exactly `expected`, a repeated root may be found once.
+	fn assert_solves(c: &[f64; 5], expected: &[f64], tolerance: f64,) {
+		let (roots, count,) = solve_quartic(c,);
+		let roots = &roots[.. count];
+		for root in roots {
+			assert!(
+				expected.iter().any(|e| (e - root).abs() < tolerance),
+				"unexpected root {} in {:?}, expected {:?}",
+				root,
+				roots,
+				expected
+			);
+		}
+		for e in expected {
+			assert!(
+				roots.iter().any(|root| (e - root).abs() < tolerance),
+				"missing root {} in {:?}",
+				e,
+				roots
+			);
+		}
+	}
+
+	fn assert_roots(roots: &[f64], complex: &[f64], tolerance: f64,) {
+		assert_solves(&from_roots(roots, complex,), roots, tolerance,);
+	}
+
+	#[test]
+	fn finds_four_distinct_roots() {
+		assert_roots(&[1.0, 2.0, 3.0, 4.0,], &[], 1e-9,);
+		assert_roots(&[-3.0, -0.5, 0.25, 7.0,], &[], 1e-9,);
+		// The distances along a ray through both sides of a torus.
+		assert_roots(&[4.5, 5.5, 9.5, 10.5,], &[], 1e-9,);
+	}
+
+	#[test]
+	fn finds_roots_of_a_depressed_quartic_without_constant() {
+		// The roots add up to zero and include zero, so the substitution leaves r = 0.
+		assert_roots(&[0.0, 1.0, 2.0, -3.0,], &[], 1e-9,);
+	}
+
+	#[test]
+	fn finds_repeated_roots() {
+		assert_roots(&[1.0, 1.0, 2.0, 3.0,], &[], 1e-6,);
+		assert_roots(&[2.0, 2.0, -1.0, -1.0,], &[], 1e-6,);
+		assert_roots(&[0.5, 0.5, 0.5, 4.0,], &[], 1e-4,);
+		// A ray grazing the torus touches it twice.
+		assert_roots(&[3.0, 3.0,], &[1.0,], 1e-6,);
+	}
+
+	#[test]
+	fn ignores_complex_roots() {
+		assert_roots(&[], &[1.0, 4.0,], 1e-9,);
+		assert_roots(&[1.0, -2.0,], &[1.0,], 1e-9,);
+	}
+
+	#[test]
+	fn scaling_the_coefficients_keeps_the_roots() {
+		let roots = [1.0, 2.0, 3.0, 4.0,];
+		assert_solves(&from_roots(&roots, &[],).map(|c| -2.5 * c,), &roots, 1e-9,);
+	}
+}
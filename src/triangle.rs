@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use crate::{
+	aabb::AABB,
+	hittable::{HitRecord, Hittable},
+	material::Material,
+	ray::Ray,
+	vec3::{cross, dot, unit_vector, Point3, Vec3},
+};
+
+pub struct Triangle {
+	pub vertices: [Point3; 3],
+	pub normals:  Option<[Vec3; 3],>,
+	pub uvs:      Option<[(f64, f64,); 3],>,
+	pub mat_ptr:  Arc<dyn Material,>,
+}
+
+impl Triangle {
+	/// Vertices are expected in counter-clockwise order seen from the front side. Without
+	/// per-vertex normals the triangle is flat shaded, without UVs the barycentric coordinates
+	/// are used.
+	pub fn new(
+		vertices: [Point3; 3],
+		normals: Option<[Vec3; 3],>,
+		uvs: Option<[(f64, f64,); 3],>,
+		mat_ptr: Arc<dyn Material,>,
+	) -> Self {
+		Triangle {
+			vertices,
+			normals,
+			uvs,
+			mat_ptr,
+		}
+	}
+}
+
+/// Möller-Trumbore ray-triangle intersection, returns `t` and the barycentric coordinates of the
+/// second and third vertex.
+#[inline]
+pub fn intersect(
+	r: &Ray,
+	vertices: &[Point3; 3],
+	t_min: f64,
+	t_max: f64,
+) -> Option<(f64, f64, f64,),> {
+	let edge1 = vertices[1] - vertices[0];
+	let edge2 = vertices[2] - vertices[0];
+	let pvec = cross(&r.direction(), &edge2,);
+	let det = dot(&edge1, &pvec,);
+	if det.abs() < 1e-12 {
+		return None;
+	}
+
+	let inv_det = 1.0 / det;
+	let tvec = r.origin() - vertices[0];
+	let b1 = dot(&tvec, &pvec,) * inv_det;
+	if !(0.0 ..= 1.0).contains(&b1,) {
+		return None;
+	}
+
+	let qvec = cross(&tvec, &edge1,);
+	let b2 = dot(&r.direction(), &qvec,) * inv_det;
+	if b2 < 0.0 || b1 + b2 > 1.0 {
+		return None;
+	}
+
+	let t = dot(&edge2, &qvec,) * inv_det;
+	if t < t_min || t > t_max {
+		return None;
+	}
+
+	Some((t, b1, b2,),)
+}
+
+/// Builds the hit record of a triangle hit at barycentric coordinates `b1`, `b2`. The front face
+/// follows the winding order, or the vertex normals when those are given.
+#[inline]
+pub fn hit_record(
+	r: &Ray,
+	t: f64,
+	(b1, b2,): (f64, f64,),
+	vertices: &[Point3; 3],
+	normals: Option<&[Vec3; 3],>,
+	uvs: Option<&[(f64, f64,); 3],>,
+	mat_ptr: &Arc<dyn Material,>,
+) -> HitRecord {
+	let b0 = 1.0 - b1 - b2;
+	let (u, v,) = match uvs {
+		Some(uvs,) => (
+			b0 * uvs[0].0 + b1 * uvs[1].0 + b2 * uvs[2].0,
+			b0 * uvs[0].1 + b1 * uvs[1].1 + b2 * uvs[2].1,
+		),
+		None => (b1, b2,),
+	};
+
+	let mut rec = HitRecord {
+		p: r.at(t,),
+		normal: Default::default(),
+		mat_ptr: mat_ptr.clone(),
+		t,
+		u,
+		v,
+		front_face: false,
+	};
+
+	let mut outward_normal = unit_vector(&cross(
+		&(vertices[1] - vertices[0]),
+		&(vertices[2] - vertices[0]),
+	),);
+
+	match normals {
+		None => rec.set_face_normal(r, &outward_normal,),
+		Some(normals,) => {
+			let shading_normal = unit_vector(&(b0 * normals[0] + b1 * normals[1] + b2 * normals[2]),);
+			if dot(&outward_normal, &shading_normal,) < 0.0 {
+				outward_normal = -outward_normal;
+			}
+			rec.set_face_normal(r, &outward_normal,);
+			rec.normal = if rec.front_face {
+				shading_normal
+			} else {
+				-shading_normal
+			};
+		},
+	}
+
+	rec
+}
+
+/// The tight box around three points, only padded along an axis the triangle is flat in.
+pub fn bounding_box(vertices: &[Point3; 3],) -> AABB {
+	let mut min = vertices[0];
+	let mut max = vertices[0];
+
+	for vertex in &vertices[1 ..] {
+		for c in 0 .. 3 {
+			min[c] = min[c].min(vertex[c],);
+			max[c] = max[c].max(vertex[c],);
+		}
+	}
+
+	for c in 0 .. 3 {
+		if max[c] - min[c] < 0.0001 {
+			min[c] -= 0.0001;
+			max[c] += 0.0001;
+		}
+	}
+
+	AABB::new(min, max,)
+}
+
+impl Hittable for Triangle {
+	fn hit(&self, r: &Ray, t_min: f64, t_max: f64,) -> Option<HitRecord,> {
+		let (t, b1, b2,) = intersect(r, &self.vertices, t_min, t_max,)?;
+
+		Some(hit_record(
+			r,
+			t,
+			(b1, b2,),
+			&self.vertices,
+			self.normals.as_ref(),
+			self.uvs.as_ref(),
+			&self.mat_ptr,
+		),)
+	}
+
+	fn bounding_box(&self, _time0: f64, _time1: f64,) -> Option<AABB,> {
+		Some(bounding_box(&self.vertices,),)
+	}
+}
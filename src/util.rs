@@ -25,3 +25,12 @@ pub fn degrees_to_radians(degrees: f64) -> f64 {
 pub fn random_i32_in_range(min: i32, max: i32) -> i32 {
 	random_f64_range(min as f64, (max + 1) as f64) as i32
 }
+
+/// Writes `contents` to a file in the temporary directory, named after this process and `name`,
+/// for tests of loaders that read from a path.
+#[cfg(test)]
+pub fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+	let path = std::env::temp_dir().join(format!("raytracing-{}-{}", std::process::id(), name));
+	std::fs::write(&path, contents).unwrap();
+	path
+}
@@ -1,5 +1,8 @@
+use std::f64::consts::PI;
 use std::fmt::{Display, Formatter, Result};
+use std::num::ParseFloatError;
 use std::ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub};
+use std::str::FromStr;
 
 use crate::util::{random_f64, random_f64_range};
 pub use Vec3 as Point3;
@@ -112,6 +115,24 @@ impl Display for Vec3 {
 	}
 }
 
+impl FromStr for Vec3 {
+	type Err = String;
+
+	/// Parses three comma separated components, e.g. `278,278,-800`.
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		let e = s
+			.split(',')
+			.map(|c| c.trim().parse())
+			.collect::<std::result::Result<Vec<f64>, ParseFloatError>>()
+			.map_err(|e| e.to_string())?;
+
+		match e[..] {
+			[x, y, z] => Ok(Vec3::new(x, y, z)),
+			_ => Err(format!("expected three components, got {}", e.len())),
+		}
+	}
+}
+
 impl Add for Vec3 {
 	type Output = Vec3;
 
@@ -241,41 +262,45 @@ pub fn unit_vector(v: &Vec3) -> Vec3 {
 	v / v.length()
 }
 
-pub fn random_in_unit_sphere() -> Vec3 {
-	loop {
-		let p = Vec3::random_in_range(-1.0, 1.0);
-		if p.length_squared() >= 1.0 {
-			continue;
-		}
-		return p;
-	}
+/// A direction uniformly distributed over all directions, from a sample `u` in the unit square.
+pub fn sample_unit_vector(u: (f64, f64)) -> Vec3 {
+	let z = 1.0 - 2.0 * u.0;
+	let r = (1.0 - z * z).max(0.0).sqrt();
+	let phi = 2.0 * PI * u.1;
+	Vec3::new(r * phi.cos(), r * phi.sin(), z)
 }
 
-pub fn random_unit_vector() -> Vec3 {
-	unit_vector(&random_in_unit_sphere())
+/// A direction around the z axis, with a density proportional to its cosine with the axis.
+pub fn sample_cosine_direction(u: (f64, f64)) -> Vec3 {
+	let phi = 2.0 * PI * u.0;
+	Vec3::new(phi.cos() * u.1.sqrt(), phi.sin() * u.1.sqrt(), (1.0 - u.1).sqrt())
 }
 
-pub fn random_in_hemisphere(normal: &Vec3) -> Vec3 {
-	let in_unit_sphere = random_in_unit_sphere();
-	if dot(&in_unit_sphere, &normal) > 0.0 {
-		in_unit_sphere
-	} else {
-		-in_unit_sphere
-	}
+/// A direction around the z axis towards a sphere of `radius` at the squared distance
+/// `distance_squared`, uniform over the solid angle the sphere covers.
+pub fn sample_to_sphere(radius: f64, distance_squared: f64, u: (f64, f64)) -> Vec3 {
+	let sin2_theta_max = radius * radius / distance_squared;
+	let z = 1.0 - u.1 * sin2_theta_max / (1.0 + (1.0 - sin2_theta_max).sqrt());
+	let phi = 2.0 * PI * u.0;
+	let sin = (1.0 - z * z).sqrt();
+	Vec3::new(phi.cos() * sin, phi.sin() * sin, z)
 }
 
-pub fn random_in_unit_disk() -> Vec3 {
-	loop {
-		let p = Vec3::new(
-			random_f64_range(-1.0, 1.0),
-			random_f64_range(-1.0, 1.0),
-			0.0,
-		);
-		if p.length_squared() >= 1.0 {
-			continue;
-		};
-		return p;
+/// A point in the unit disk in the xy plane. The concentric mapping keeps points that are close
+/// in the unit square close in the disk, which preserves how evenly samples are spread.
+pub fn sample_unit_disk(u: (f64, f64)) -> Vec3 {
+	let a = 2.0 * u.0 - 1.0;
+	let b = 2.0 * u.1 - 1.0;
+	if a == 0.0 && b == 0.0 {
+		return Vec3::default();
 	}
+
+	let (r, theta) = if a.abs() > b.abs() {
+		(a, PI / 4.0 * (b / a))
+	} else {
+		(b, PI / 2.0 - PI / 4.0 * (a / b))
+	};
+	Vec3::new(r * theta.cos(), r * theta.sin(), 0.0)
 }
 
 pub fn reflect(v: &Vec3, n: &Vec3) -> Vec3 {